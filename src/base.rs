@@ -16,7 +16,7 @@ use crate::error;
 /// This trait defines some base types of underlying git implementations.
 pub trait Base {
     /// Type used for representing Object IDs
-    type Oid: Clone + fmt::Debug + fmt::Display + Eq + Hash;
+    type Oid: Clone + fmt::Debug + fmt::Display + Ord + Hash;
 
     /// (Inner) error type associated with this entity
     type InnerError: error::InnerError<Oid = Self::Oid>;
@@ -26,7 +26,7 @@ impl<A, B, O, E> Base for (A, B)
 where
     A: Base<Oid = O, InnerError = E>,
     B: Base<Oid = O, InnerError = E>,
-    O: Clone + fmt::Debug + fmt::Display + Eq + Hash,
+    O: Clone + fmt::Debug + fmt::Display + Ord + Hash,
     E: error::InnerError<Oid = O>,
 {
     type Oid = O;
@@ -39,55 +39,38 @@ impl Base for git2::Repository {
     type InnerError = git2::Error;
 }
 
+#[cfg(feature = "gix")]
+impl Base for gix::Repository {
+    type Oid = gix::ObjectId;
+    type InnerError = error::GixError;
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
-    use super::*;
-
-    #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-    pub struct TestOid([u8; 20]);
-
-    impl std::ops::AddAssign<u8> for TestOid {
-        fn add_assign(&mut self, rhs: u8) {
-            self.0.iter_mut().rev().fold(rhs, |c, n| {
-                let [h, l] = (c as u16 + *n as u16).to_be_bytes();
-                *n = l;
-                h
-            });
-        }
-    }
-
-    impl fmt::Display for TestOid {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            self.0.into_iter().try_for_each(|c| write!(f, "{c:02x}"))
-        }
-    }
-
-    impl std::str::FromStr for TestOid {
-        type Err = String;
-
-        fn from_str(mut s: &str) -> Result<Self, Self::Err> {
-            let mut res: [u8; 20] = Default::default();
-            for i in 0..20 {
-                let (part, rest) = s.split_at(2);
-                res[i] = u8::from_str_radix(part, 16).map_err(|_| format!("Not hex: {part}"))?;
-                s = rest;
-            }
-            Ok(TestOid(res))
-        }
-    }
-
-    impl PartialEq<&str> for TestOid {
-        fn eq(&self, other: &&str) -> bool {
-            other.parse().map(|i: Self| self == &i).unwrap_or(false)
-        }
+    /// Create a `git2` repository in a temporary directory
+    #[cfg(feature = "git2")]
+    pub(crate) fn git2_repository() -> (tempfile::TempDir, git2::Repository) {
+        let dir = tempfile::tempdir().expect("Could not create temporary directory");
+        let repo = git2::Repository::init_bare(dir.path()).expect("Could not create repository");
+        (dir, repo)
     }
 
-    #[test]
-    fn test_oid_incremental_order() {
-        let mut id = TestOid::default();
-        id += 1; // id = [1, 0, 0, ...]
-        let one = id.clone();
-        id += 255; // id = [0, 1, 0, ...]
-        assert!(one < id);
+    /// Create a `gix` repository with a configured identity in a temporary directory
+    #[cfg(feature = "gix")]
+    pub(crate) fn gix_repository() -> (tempfile::TempDir, gix::Repository) {
+        let dir = tempfile::tempdir().expect("Could not create temporary directory");
+        gix::init_bare(dir.path()).expect("Could not create repository");
+        let mut config = std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("config"))
+            .expect("Could not open config");
+        std::io::Write::write_all(
+            &mut config,
+            b"[user]\n\tname = Foo Bar\n\temail = foo@example.com\n",
+        )
+        .expect("Could not write config");
+        let repo = gix::open_opts(dir.path(), gix::open::Options::isolated())
+            .expect("Could not open repository");
+        (dir, repo)
     }
 }
@@ -0,0 +1,476 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Metadata caching
+//!
+//! This module provides the [MetadataCache], which keeps the accumulated
+//! metadata of issues alongside the heads it was computed from. Cached metadata
+//! is only used as long as neither the local head nor any remote head of the
+//! issue moved and the trailer specifications requested did not change.
+//!
+//! A cache may be persisted in a simple line based text format. The first line
+//! identifies the format and its version. Each issue is introduced by a line
+//! consisting of the keyword `issue`, the issue's id and the targets of its
+//! heads. Each following line starting with the keyword `trailer` holds the
+//! key, the accumulation policy and the accumulated values for one trailer.
+//! Fields are separated by tabs. Tabs, newlines and backslashes within fields
+//! are escaped with a backslash.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error;
+use crate::issue::Issue;
+use crate::object::Database;
+use crate::reference::{Reference, Store};
+use crate::trailer::TrailerValue;
+use crate::trailer::accumulation::ValueAccumulator;
+use crate::trailer::spec::ToMap;
+use crate::traversal::Traversible;
+use crate::utils::LineFormat;
+
+/// Accumulated metadata of a single issue
+pub type Metadata = HashMap<String, ValueAccumulator>;
+
+/// Path of the cache file relative to a repository's git directory
+pub const CACHE_PATH: &str = "dit/metadata-cache";
+
+/// Format of a persisted cache
+const FORMAT: LineFormat = LineFormat {
+    header: "dit-metadata-cache 1",
+    name: "metadata cache",
+};
+
+/// Cache for accumulated metadata of issues
+///
+/// Each entry consists of the metadata of an issue as well as the targets of
+/// the issue's local and remote heads at the time the metadata was accumulated.
+#[derive(Clone)]
+pub struct MetadataCache<O> {
+    entries: HashMap<O, Entry<O>>,
+}
+
+impl<O: Clone + Eq + Hash> MetadataCache<O> {
+    /// Create a new, empty cache
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Retrieve the metadata of an issue
+    ///
+    /// If the cache holds metadata for the issue which was accumulated from the
+    /// current heads using the same `specs`, that metadata is returned.
+    /// Otherwise, the metadata is accumulated from the issue's local head or,
+    /// if there is none, the head of the first remote carrying the issue, and
+    /// stored in the cache. Returns `None` if the issue has no head.
+    pub fn metadata<'r, R, S>(
+        &mut self,
+        issue: &Issue<'r, R>,
+        specs: S,
+    ) -> error::Result<Option<Metadata>, R::InnerError>
+    where
+        R: Store<'r, Oid = O> + Database<'r> + Traversible<'r>,
+        S: ToMap<Output = Metadata> + Clone,
+    {
+        let heads = issue
+            .all_heads()?
+            .filter_map(|r| r.map(|r| r.target()).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+        let Some(head) = heads.first().cloned() else {
+            self.entries.remove(issue.id());
+            return Ok(None);
+        };
+
+        let template = specs.clone().into_map();
+        let cached = self
+            .entries
+            .get(issue.id())
+            .filter(|e| e.heads == heads && same_shape(&e.metadata, &template));
+        if let Some(entry) = cached {
+            return Ok(Some(entry.metadata.clone()));
+        }
+
+        let metadata = issue.metadata_from(head, specs)?;
+        let entry = Entry {
+            heads,
+            metadata: metadata.clone(),
+        };
+        self.entries.insert(issue.id().clone(), entry);
+        Ok(Some(metadata))
+    }
+
+    /// Only retain entries for issues satisfying a predicate
+    pub fn retain(&mut self, mut predicate: impl FnMut(&O) -> bool) {
+        self.entries.retain(|id, _| predicate(id))
+    }
+
+    /// Retrieve the number of issues in the cache
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<O> MetadataCache<O>
+where
+    O: Clone + Eq + Hash + fmt::Display + FromStr,
+{
+    /// Load a cache from a file
+    ///
+    /// If the file does not exist, an empty cache is returned.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        FORMAT.load(path, Self::read)
+    }
+
+    /// Save the cache to a file
+    ///
+    /// The file is replaced atomically, so a cache shared between processes
+    /// is never observed partially written.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        FORMAT.save(path, |file| self.write(file))
+    }
+
+    /// Read a cache in the persisted format
+    pub fn read(reader: impl BufRead) -> io::Result<Self> {
+        let invalid = |line| FORMAT.invalid(line);
+        let Some(lines) = FORMAT.lines(reader)? else {
+            return Ok(Self::new());
+        };
+
+        let mut entries = HashMap::new();
+        let mut current: Option<(O, Entry<O>)> = None;
+        for (n, line) in lines {
+            let line = line?;
+            let mut fields = line.split('\t').map(unescape);
+            match fields.next().as_deref() {
+                Some("issue") => {
+                    let ids: Option<Vec<O>> = fields.map(|f| f.parse().ok()).collect();
+                    let mut ids = ids.ok_or_else(|| invalid(n))?.into_iter();
+                    let id = ids.next().ok_or_else(|| invalid(n))?;
+                    let entry = Entry {
+                        heads: ids.collect(),
+                        metadata: Default::default(),
+                    };
+                    entries.extend(current.replace((id, entry)));
+                }
+                Some("trailer") => {
+                    let (_, entry) = current.as_mut().ok_or_else(|| invalid(n))?;
+                    let key = fields.next().ok_or_else(|| invalid(n))?;
+                    let policy = fields.next().ok_or_else(|| invalid(n))?;
+                    let values: Vec<_> = fields.collect();
+                    let acc = parse_accumulator(&policy, values).ok_or_else(|| invalid(n))?;
+                    entry.metadata.insert(key, acc);
+                }
+                _ => return Err(invalid(n)),
+            }
+        }
+        entries.extend(current);
+        Ok(Self { entries })
+    }
+
+    /// Write the cache in the persisted format
+    ///
+    /// Issues are written in the order of their ids, trailers in the order of
+    /// their keys.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        FORMAT.write_header(&mut writer)?;
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(id, entry)| (id.to_string(), entry))
+            .collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        for (id, entry) in entries {
+            write!(writer, "issue\t{}", escape(&id))?;
+            for head in &entry.heads {
+                write!(writer, "\t{}", escape(&head.to_string()))?;
+            }
+            writeln!(writer)?;
+
+            let mut metadata: Vec<_> = entry.metadata.iter().collect();
+            metadata.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            for (key, acc) in metadata {
+                write!(writer, "trailer\t{}", escape(key))?;
+                write_accumulator(&mut writer, acc)?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<O> Default for MetadataCache<O> {
+    fn default() -> Self {
+        Self {
+            entries: Default::default(),
+        }
+    }
+}
+
+/// Cached metadata of a single issue
+#[derive(Clone)]
+struct Entry<O> {
+    heads: Vec<O>,
+    metadata: Metadata,
+}
+
+/// Check whether two metadata maps have the same keys and policies
+fn same_shape(lhs: &Metadata, rhs: &Metadata) -> bool {
+    use std::mem::discriminant;
+
+    lhs.len() == rhs.len()
+        && lhs.iter().all(|(k, v)| {
+            rhs.get(k)
+                .map(|o| discriminant(o) == discriminant(v))
+                .unwrap_or(false)
+        })
+}
+
+/// Write the policy and the values of an accumulator
+fn write_accumulator(writer: &mut impl Write, acc: &ValueAccumulator) -> io::Result<()> {
+    let (policy, values): (_, Vec<_>) = match acc {
+        ValueAccumulator::Latest(v) => ("latest", v.iter().map(value_field).collect()),
+        ValueAccumulator::List(v) => ("list", v.iter().map(value_field).collect()),
+        ValueAccumulator::First(v) => ("first", v.iter().map(value_field).collect()),
+        ValueAccumulator::Set(v) => ("set", v.iter().map(value_field).collect()),
+        ValueAccumulator::Count(n) => ("count", vec![n.to_string()]),
+        ValueAccumulator::Labels(l) => (
+            "labels",
+            l.iter()
+                .map(|(l, set)| format!("{}{l}", if *set { '+' } else { '-' }))
+                .collect(),
+        ),
+    };
+    write!(writer, "\t{policy}")?;
+    values
+        .iter()
+        .try_for_each(|v| write!(writer, "\t{}", escape(v)))
+}
+
+/// Parse an accumulator from its policy and values
+fn parse_accumulator(policy: &str, values: Vec<String>) -> Option<ValueAccumulator> {
+    let parse_values = || {
+        values
+            .iter()
+            .map(|v| parse_value(v))
+            .collect::<Option<Vec<_>>>()
+    };
+    let single = || match parse_values()?.as_slice() {
+        [] => Some(None),
+        [v] => Some(Some(v.clone())),
+        _ => None,
+    };
+    let acc = match policy {
+        "latest" => ValueAccumulator::Latest(single()?),
+        "list" => ValueAccumulator::List(parse_values()?),
+        "first" => ValueAccumulator::First(single()?),
+        "set" => ValueAccumulator::Set(parse_values()?.into_iter().collect()),
+        "count" => match values.as_slice() {
+            [n] => ValueAccumulator::Count(n.parse().ok()?),
+            _ => return None,
+        },
+        "labels" => ValueAccumulator::Labels(
+            values
+                .iter()
+                .map(|v| match v.split_at_checked(1)? {
+                    ("+", l) => Some((l.to_owned(), true)),
+                    ("-", l) => Some((l.to_owned(), false)),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+        ),
+        _ => return None,
+    };
+    Some(acc)
+}
+
+/// Format a value, preserving its variant
+fn value_field(value: &TrailerValue) -> String {
+    match value {
+        TrailerValue::Int(i) => format!("i{i}"),
+        TrailerValue::String(s) => format!("s{s}"),
+    }
+}
+
+/// Parse a value formatted via [value_field]
+fn parse_value(field: &str) -> Option<TrailerValue> {
+    match field.split_at_checked(1)? {
+        ("i", i) => i.parse().ok().map(TrailerValue::Int),
+        ("s", s) => Some(TrailerValue::String(s.to_owned())),
+        _ => None,
+    }
+}
+
+/// Escape tabs, newlines and backslashes in a field
+fn escape(field: &str) -> String {
+    let mut res = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '\t' => res.push_str("\\t"),
+            '\n' => res.push_str("\\n"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Unescape a field escaped via [escape]
+fn unescape(field: &str) -> String {
+    let mut res = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => res.push('\t'),
+            Some('n') => res.push('\n'),
+            Some(c) => res.push(c),
+            None => res.push('\\'),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory;
+    use crate::repository::Repository;
+    use crate::trailer::spec::{ISSUE_LABEL_SPEC, ISSUE_STATUS_SPEC};
+
+    type TestRepo = memory::Repository;
+
+    fn values(metadata: &Metadata, key: &str) -> Vec<TrailerValue> {
+        metadata
+            .get(key)
+            .cloned()
+            .map(|v| v.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn invalidation() {
+        let repo = TestRepo::default();
+        let specs = [ISSUE_STATUS_SPEC];
+
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test message 1\n\nDit-status: open")
+            .expect("Could not create issue");
+        let mut cache = MetadataCache::new();
+        let metadata = cache
+            .metadata(&issue, &specs)
+            .expect("Could not get metadata")
+            .expect("No metadata");
+        assert_eq!(
+            values(&metadata, "Dit-status"),
+            [TrailerValue::from_slice("open")]
+        );
+        assert_eq!(cache.len(), 1);
+
+        let closing = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(issue.initial_message().expect("No initial message"))
+            .build("Test message 2\n\nDit-status: closed")
+            .expect("Could not add message");
+        let metadata = cache
+            .metadata(&issue, &specs)
+            .expect("Could not get metadata")
+            .expect("No metadata");
+        assert_eq!(
+            values(&metadata, "Dit-status"),
+            [TrailerValue::from_slice("open")]
+        );
+
+        issue
+            .update_head(closing, true)
+            .expect("Could not update head");
+        let metadata = cache
+            .metadata(&issue, &specs)
+            .expect("Could not get metadata")
+            .expect("No metadata");
+        assert_eq!(
+            values(&metadata, "Dit-status"),
+            [TrailerValue::from_slice("closed")]
+        );
+
+        let metadata = cache
+            .metadata(&issue, &[ISSUE_STATUS_SPEC, ISSUE_LABEL_SPEC])
+            .expect("Could not get metadata")
+            .expect("No metadata");
+        assert!(metadata.contains_key("Dit-label"));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let repo = TestRepo::default();
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test\n\nDit-status: in\\ progress\nDit-label: +a\nDit-label: -b\nDit-status: 3")
+            .expect("Could not create issue");
+        let specs = [ISSUE_STATUS_SPEC, ISSUE_LABEL_SPEC];
+
+        let mut cache = MetadataCache::new();
+        let metadata = cache
+            .metadata(&issue, &specs)
+            .expect("Could not get metadata")
+            .expect("No metadata");
+
+        let mut data = Vec::new();
+        cache.write(&mut data).expect("Could not write cache");
+        let mut cache =
+            MetadataCache::<memory::Oid>::read(data.as_slice()).expect("Could not read cache");
+        assert_eq!(cache.len(), 1);
+
+        let cached = cache.entries.get(issue.id()).expect("No entry").clone();
+        assert_eq!(cached.heads, [*issue.id()]);
+        for key in ["Dit-status", "Dit-label"] {
+            assert_eq!(values(&cached.metadata, key), values(&metadata, key));
+        }
+        let labels = match cached.metadata.get("Dit-label") {
+            Some(ValueAccumulator::Labels(l)) => l.clone(),
+            _ => panic!("Unexpected accumulator"),
+        };
+        assert_eq!(labels.get("b"), Some(&false));
+
+        let mut rewritten = Vec::new();
+        cache.write(&mut rewritten).expect("Could not write cache");
+        assert_eq!(rewritten, data);
+        let text = String::from_utf8(data).expect("Invalid cache");
+        let label = text.find("trailer\tDit-label").expect("No label trailer");
+        let status = text.find("trailer\tDit-status").expect("No status trailer");
+        assert!(label < status);
+
+        cache
+            .metadata(&issue, &specs)
+            .expect("Could not get metadata")
+            .expect("No metadata");
+        assert!(MetadataCache::<memory::Oid>::read("foo\n".as_bytes()).is_err());
+        assert!(MetadataCache::<memory::Oid>::read("".as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn escaping() {
+        let text = "a\tb\\c\nd\\t";
+        assert_eq!(unescape(&escape(text)), text);
+        assert!(!escape(text).contains(['\t', '\n']));
+    }
+}
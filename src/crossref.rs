@@ -0,0 +1,268 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Cross references
+//!
+//! While the first parent of a message is the message it replies to, any
+//! additional parents may refer to arbitrary commits: messages of other issues
+//! or commits in the project's history. This module provides the [Resolver]
+//! for classifying those references.
+
+use crate::base::Base;
+use crate::error::{self, ResultExt};
+use crate::index::MessageIndex;
+use crate::issue::Issue;
+use crate::object::Database;
+use crate::object::commit::Commit;
+use crate::repository::Repository;
+use crate::traversal::Traversible;
+
+/// Target of a reference
+pub enum Target<'r, R: Base> {
+    /// A message of an issue
+    Message {
+        /// The issue containing the message
+        issue: Issue<'r, R>,
+        /// The message referenced
+        message: R::Oid,
+    },
+    /// A commit which is not part of any issue
+    Code(R::Oid),
+    /// A commit which is not present in the repository
+    Unknown(R::Oid),
+}
+
+impl<R: Base> Target<'_, R> {
+    /// Retrieve the id of the commit referenced
+    pub fn id(&self) -> &R::Oid {
+        match self {
+            Self::Message { message, .. } => message,
+            Self::Code(id) => id,
+            Self::Unknown(id) => id,
+        }
+    }
+}
+
+/// Resolver for references
+///
+/// Commits are resolved to issues via
+/// [Repository::issue_with_message_indexed]. The resolver maintains the
+/// [MessageIndex] used for the lookups.
+pub struct Resolver<'r, R: Base> {
+    repo: &'r R,
+    index: MessageIndex<R::Oid>,
+}
+
+impl<'r, R> Resolver<'r, R>
+where
+    R: Repository<'r> + Database<'r> + Traversible<'r>,
+{
+    /// Create a new resolver for a repository
+    pub fn new(repo: &'r R) -> Self {
+        Self::with_index(repo, Default::default())
+    }
+
+    /// Create a new resolver using an existing [MessageIndex]
+    pub fn with_index(repo: &'r R, index: MessageIndex<R::Oid>) -> Self {
+        Self { repo, index }
+    }
+
+    /// Extract the [MessageIndex] used by this resolver
+    pub fn into_index(self) -> MessageIndex<R::Oid> {
+        self.index
+    }
+
+    /// Resolve a single commit
+    pub fn resolve(&mut self, id: R::Oid) -> error::Result<Target<'r, R>, R::InnerError> {
+        match self.repo.find_commit(id.clone()) {
+            Ok(_) => (),
+            Err(e) if e.is_not_found() => return Ok(Target::Unknown(id)),
+            Err(e) => return Err(e),
+        }
+
+        match self
+            .repo
+            .issue_with_message_indexed(&mut self.index, id.clone())
+        {
+            Ok(issue) => Ok(Target::Message { issue, message: id }),
+            Err(e) if matches!(e.kind(), error::Kind::NoTreeInitFound(_)) => Ok(Target::Code(id)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Resolve the references of a message
+    ///
+    /// Each parent of the message except the first one is resolved.
+    pub fn references(
+        &mut self,
+        message: &R::Commit,
+    ) -> error::Result<Vec<Target<'r, R>>, R::InnerError> {
+        message
+            .parent_ids()
+            .into_iter()
+            .skip(1)
+            .map(|p| self.resolve(p))
+            .collect()
+    }
+
+    /// Find all issues with messages referring to a specific commit
+    ///
+    /// Only parents other than the first one are considered references.
+    /// Issues are returned in the order of their ids.
+    pub fn issues_referencing_commit(
+        &mut self,
+        commit: &R::Oid,
+    ) -> error::Result<Vec<Issue<'r, R>>, R::InnerError> {
+        self.referencing_issues(|_, parent| parent == commit)
+    }
+
+    /// Find all other issues with messages referring to a message of an issue
+    ///
+    /// Only parents other than the first one are considered references.
+    /// Issues are returned in the order of their ids.
+    pub fn issues_referencing_issue(
+        &mut self,
+        issue: &Issue<'r, R>,
+    ) -> error::Result<Vec<Issue<'r, R>>, R::InnerError> {
+        self.index.update(self.repo)?;
+        self.referencing_issues(|index, parent| index.get(parent) == Some(issue.id()))
+            .map(|mut res| {
+                res.retain(|i| i != issue);
+                res
+            })
+    }
+
+    /// Find all issues with messages referring to commits matching a predicate
+    fn referencing_issues(
+        &mut self,
+        predicate: impl Fn(&MessageIndex<R::Oid>, &R::Oid) -> bool,
+    ) -> error::Result<Vec<Issue<'r, R>>, R::InnerError> {
+        let mut res = Vec::new();
+        for issue in self.repo.issues()? {
+            for message in issue.messages()? {
+                let message = message
+                    .map_err(Into::into)
+                    .wrap_with_kind(error::Kind::CannotGetCommit)?;
+                let matching = self
+                    .repo
+                    .find_commit(message)?
+                    .parent_ids()
+                    .into_iter()
+                    .skip(1)
+                    .any(|p| predicate(&self.index, &p));
+                if matching {
+                    res.push(issue);
+                    break;
+                }
+            }
+        }
+        res.sort_unstable_by(|a, b| a.id().cmp(b.id()));
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory;
+
+    type TestRepo = memory::Repository;
+
+    #[test]
+    fn resolve() {
+        let repo = TestRepo::default();
+        let new_issue = || {
+            repo.issue_builder()
+                .expect("Could not create issue builder")
+                .build("Test message")
+                .expect("Could not create issue")
+        };
+        let reply = |issue: &Issue<'_, TestRepo>, parents: &[memory::Oid]| {
+            issue
+                .message_builder()
+                .expect("Could not create builder")
+                .with_parent(issue.initial_message().expect("No initial message"))
+                .with_parents(
+                    parents
+                        .iter()
+                        .map(|p| repo.find_commit(*p).expect("No such commit")),
+                )
+                .build("Reply")
+                .expect("Could not add message")
+        };
+
+        let code = repo
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .build("Code")
+            .expect("Cannot create commit")
+            .id();
+        let first = new_issue();
+        let discussion = reply(&first, &[]);
+        let second = new_issue();
+        let citing = reply(&second, &[discussion, code]);
+        let third = new_issue();
+        reply(&third, &[code]);
+
+        let mut resolver = Resolver::new(&repo);
+        let targets = resolver
+            .references(&repo.find_commit(citing).expect("No such message"))
+            .expect("Could not resolve references");
+        assert_eq!(targets.len(), 2);
+        match &targets[0] {
+            Target::Message { issue, message } => {
+                assert_eq!(issue.id(), first.id());
+                assert_eq!(message, &discussion);
+            }
+            _ => panic!("Unexpected target"),
+        }
+        assert!(matches!(targets[1], Target::Code(c) if c == code));
+        assert!(matches!(
+            resolver.resolve(memory::Oid::default()),
+            Ok(Target::Unknown(_))
+        ));
+
+        let issues: Vec<_> = resolver
+            .issues_referencing_commit(&code)
+            .expect("Could not find issues")
+            .iter()
+            .map(|i| *i.id())
+            .collect();
+        assert_eq!(issues, [*second.id(), *third.id()]);
+        let issues: Vec<_> = resolver
+            .issues_referencing_issue(&first)
+            .expect("Could not find issues")
+            .iter()
+            .map(|i| *i.id())
+            .collect();
+        assert_eq!(issues, [*second.id()]);
+        let issues = resolver
+            .issues_referencing_issue(&second)
+            .expect("Could not find issues");
+        assert!(issues.is_empty());
+    }
+
+    #[cfg(feature = "git2")]
+    #[test]
+    fn git2_unknown() {
+        let (_dir, repo) = crate::base::tests::git2_repository();
+        let target = Resolver::new(&repo)
+            .resolve(git2::Oid::zero())
+            .expect("Could not resolve commit");
+        assert!(matches!(target, Target::Unknown(_)));
+    }
+
+    #[cfg(feature = "gix")]
+    #[test]
+    fn gix_unknown() {
+        let (_dir, repo) = crate::base::tests::gix_repository();
+        let target = Resolver::new(&repo)
+            .resolve(gix::ObjectId::null(gix::hash::Kind::Sha1))
+            .expect("Could not resolve commit");
+        assert!(matches!(target, Target::Unknown(_)));
+    }
+}
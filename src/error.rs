@@ -7,6 +7,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 //
 
+use std::convert::Infallible;
 use std::fmt;
 use std::str::Utf8Error;
 
@@ -63,6 +64,13 @@ impl<I: InnerError> Error<I> {
     pub fn kind(&self) -> &Kind<I> {
         &self.kind
     }
+
+    /// Check whether the error was caused by an entity not being present
+    ///
+    /// See [InnerError::is_not_found].
+    pub fn is_not_found(&self) -> bool {
+        matches!(&self.inner, Some(Inner::Error(i)) if i.is_not_found())
+    }
 }
 
 impl<I: InnerError> From<Kind<I>> for Error<I> {
@@ -135,6 +143,8 @@ pub enum Kind<I: InnerError> {
     CannotGetTree,
     CannotCreateTreeBuilder,
     CannotBuildTree,
+    CannotWriteBlob,
+    CannotGetBlob,
     CannotGetSignature,
     CannotFindIssueHead(I::Oid),
     CannotSetReference(String),
@@ -143,6 +153,9 @@ pub enum Kind<I: InnerError> {
     MalFormedHeadReference(I::RefName),
     TrailerFormatError(String),
     MalformedMessage,
+    CannotWriteMbox,
+    InvalidDocument(String),
+    CannotWriteSite,
 }
 
 impl<I: InnerError> Kind<I> {
@@ -152,6 +165,12 @@ impl<I: InnerError> Kind<I> {
     }
 }
 
+impl<I: InnerError> From<Infallible> for Kind<I> {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
 impl<I: InnerError> fmt::Display for Kind<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -171,6 +190,8 @@ impl<I: InnerError> fmt::Display for Kind<I> {
             Self::CannotGetTree => write!(f, "cannot get a specific tree from repository"),
             Self::CannotCreateTreeBuilder => write!(f, "cannot create a tree builder"),
             Self::CannotBuildTree => write!(f, "cannot build Tree"),
+            Self::CannotWriteBlob => write!(f, "cannot write a blob"),
+            Self::CannotGetBlob => write!(f, "cannot get a specific blob from repository"),
             Self::CannotGetSignature => write!(f, "cannot retrieve signature"),
             Self::CannotFindIssueHead(i) => write!(f, "cannot find issue HEAD for {i}"),
             Self::CannotSetReference(r) => write!(f, "cannot update or create reference '{r}'"),
@@ -179,6 +200,9 @@ impl<I: InnerError> fmt::Display for Kind<I> {
             Self::MalFormedHeadReference(n) => write!(f, "malformed head refernece: {n}"),
             Self::TrailerFormatError(t) => write!(f, "malformed trailer: {t}"),
             Self::MalformedMessage => write!(f, "malformed message"),
+            Self::CannotWriteMbox => write!(f, "cannot write mbox"),
+            Self::InvalidDocument(d) => write!(f, "invalid issue document: {d}"),
+            Self::CannotWriteSite => write!(f, "cannot write site"),
         }
     }
 }
@@ -195,31 +219,75 @@ pub trait InnerError: std::error::Error {
 
     /// Type used for representing refs in actual [Error]s
     type RefName: Clone + fmt::Debug + fmt::Display;
+
+    /// Check whether this error indicates that an entity is not present
+    ///
+    /// An entity may be an object or a reference, for example.
+    fn is_not_found(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(feature = "git2")]
 impl InnerError for git2::Error {
     type Oid = git2::Oid;
     type RefName = String;
+
+    fn is_not_found(&self) -> bool {
+        self.code() == git2::ErrorCode::NotFound
+    }
 }
 
-#[cfg(test)]
-pub(crate) mod tests {
-    use super::*;
+/// [InnerError] for the `gix` backend
+///
+/// Unlike `git2`, `gix` does not provide a single error type. This type wraps
+/// any of the errors emitted by `gix` functions.
+#[cfg(feature = "gix")]
+#[derive(Clone, Debug)]
+pub struct GixError(std::sync::Arc<dyn std::error::Error + Send + Sync>);
 
-    #[derive(Copy, Clone, Debug, PartialEq)]
-    pub struct TestError;
+#[cfg(feature = "gix")]
+impl GixError {
+    /// Wrap an error emitted by `gix`
+    pub(crate) fn new(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(err))
+    }
+}
 
-    impl InnerError for TestError {
-        type Oid = crate::base::tests::TestOid;
-        type RefName = String;
+#[cfg(feature = "gix")]
+impl From<Box<dyn std::error::Error + Send + Sync>> for GixError {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self(err.into())
     }
+}
 
-    impl std::error::Error for TestError {}
+#[cfg(feature = "gix")]
+impl std::error::Error for GixError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.0)
+    }
+}
 
-    impl fmt::Display for TestError {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            fmt::Display::fmt("TEST ERROR", f)
-        }
+#[cfg(feature = "gix")]
+impl fmt::Display for GixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "gix")]
+impl InnerError for GixError {
+    type Oid = gix::ObjectId;
+    type RefName = String;
+
+    fn is_not_found(&self) -> bool {
+        use gix::object::find::existing::{Error, with_conversion};
+
+        let err = &*self.0;
+        matches!(err.downcast_ref(), Some(Error::NotFound { .. }))
+            || matches!(
+                err.downcast_ref(),
+                Some(with_conversion::Error::Find(Error::NotFound { .. }))
+            )
     }
 }
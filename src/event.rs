@@ -0,0 +1,334 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Reference change events
+//!
+//! This module provides the [Snapshot] type for recording the state of all
+//! dit references of a repository, both local and remote. Comparing two
+//! snapshots, e.g. one taken before and one taken after a fetch, yields a
+//! series of [Event]s describing the changes. These may be used for notifying
+//! maintainers or contributors about new issues and messages.
+//!
+//! Events are derived purely from references. A [Event::NewMessage] is thus
+//! reported for each new leaf reference, not for every message which became
+//! reachable.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::{self, ResultExt};
+use crate::issue::DIT_REF_PART;
+use crate::reference::{self, Reference, Store};
+use crate::remote::{Name, Names};
+
+/// Location of a set of references
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Origin {
+    /// The local repository
+    Local,
+    /// A remote with a given name
+    Remote(String),
+}
+
+/// References of a single issue at one [Origin]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IssueRefs<O> {
+    /// Target of the issue's head reference
+    pub head: Option<O>,
+    /// Targets of the issue's leaf references
+    pub leaves: BTreeSet<O>,
+}
+
+impl<O> Default for IssueRefs<O> {
+    fn default() -> Self {
+        Self {
+            head: None,
+            leaves: Default::default(),
+        }
+    }
+}
+
+/// A change of references
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event<O> {
+    /// An issue appeared at an origin
+    NewIssue { origin: Origin, issue: O },
+    /// A message of an issue is now referenced by a leaf
+    NewMessage {
+        origin: Origin,
+        issue: O,
+        message: O,
+    },
+    /// The head reference of an issue was created, updated or removed
+    HeadMoved {
+        origin: Origin,
+        issue: O,
+        /// Previous target of the head reference
+        old: Option<O>,
+        /// Current target of the head reference
+        new: Option<O>,
+    },
+    /// A leaf reference of an issue was removed
+    LeafRemoved {
+        origin: Origin,
+        issue: O,
+        message: O,
+    },
+    /// All references of an issue were removed from an origin
+    IssueDisappeared { origin: Origin, issue: O },
+}
+
+/// Snapshot of the dit references of a repository
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot<O> {
+    issues: BTreeMap<(Origin, O), IssueRefs<O>>,
+}
+
+impl<O: Clone + Ord> Snapshot<O> {
+    /// Take a snapshot of all local and remote dit references of a repository
+    pub fn capture<'r, R>(repo: &'r R) -> error::Result<Self, R::InnerError>
+    where
+        R: Store<'r, Oid = O>,
+    {
+        let mut origins = vec![(Origin::Local, "refs".to_owned())];
+        for name in repo.remote_names()?.names() {
+            let prefix = name
+                .ref_path()
+                .wrap_with_kind(error::Kind::ReferenceNameError)?;
+            let name = name
+                .as_str()
+                .wrap_with_kind(error::Kind::ReferenceNameError)?;
+            origins.push((Origin::Remote(name.to_owned()), prefix));
+        }
+
+        let mut snapshot = Self::default();
+        for (origin, prefix) in origins {
+            let path = format!("{prefix}/{DIT_REF_PART}");
+            for reference in repo.references(path.as_ref())? {
+                let reference = reference.wrap_with_kind(error::Kind::CannotGetReference)?;
+                if let (Some(parts), Some(target)) = (reference.parts(), reference.target()) {
+                    snapshot.insert(origin.clone(), parts.issue, parts.kind, target);
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Record a reference
+    ///
+    /// Any head reference of the issue already recorded for the given origin
+    /// is replaced.
+    pub fn insert(&mut self, origin: Origin, issue: O, kind: reference::Kind<O>, target: O) {
+        let refs = self.issues.entry((origin, issue)).or_default();
+        match kind {
+            reference::Kind::Head => refs.head = Some(target),
+            reference::Kind::Leaf(_) => {
+                refs.leaves.insert(target);
+            }
+        }
+    }
+
+    /// Retrieve the references of an issue at a given origin
+    pub fn get(&self, origin: &Origin, issue: &O) -> Option<&IssueRefs<O>> {
+        self.issues.get(&(origin.clone(), issue.clone()))
+    }
+
+    /// Iterate over all issues recorded, along with their origin
+    pub fn iter(&self) -> impl Iterator<Item = (&Origin, &O, &IssueRefs<O>)> + '_ {
+        self.issues.iter().map(|((o, i), r)| (o, i, r))
+    }
+
+    /// Compute the events leading from this snapshot to a newer one
+    ///
+    /// Events are grouped by origin and issue, in that order. For each issue,
+    /// a [Event::NewIssue] precedes all other events. An issue which
+    /// disappeared is reported via a single [Event::IssueDisappeared].
+    pub fn events(&self, newer: &Self) -> Vec<Event<O>> {
+        let keys: BTreeSet<_> = self.issues.keys().chain(newer.issues.keys()).collect();
+        let empty = IssueRefs::default();
+
+        let mut events = Vec::new();
+        for (origin, issue) in keys {
+            let key = (origin.clone(), issue.clone());
+            let (old, new) = match (self.issues.get(&key), newer.issues.get(&key)) {
+                (Some(_), None) => {
+                    events.push(Event::IssueDisappeared {
+                        origin: origin.clone(),
+                        issue: issue.clone(),
+                    });
+                    continue;
+                }
+                (None, Some(new)) => {
+                    events.push(Event::NewIssue {
+                        origin: origin.clone(),
+                        issue: issue.clone(),
+                    });
+                    (&empty, new)
+                }
+                (Some(old), Some(new)) => (old, new),
+                (None, None) => continue,
+            };
+
+            if old.head != new.head {
+                events.push(Event::HeadMoved {
+                    origin: origin.clone(),
+                    issue: issue.clone(),
+                    old: old.head.clone(),
+                    new: new.head.clone(),
+                });
+            }
+            events.extend(
+                new.leaves
+                    .difference(&old.leaves)
+                    .map(|message| Event::NewMessage {
+                        origin: origin.clone(),
+                        issue: issue.clone(),
+                        message: message.clone(),
+                    }),
+            );
+            events.extend(
+                old.leaves
+                    .difference(&new.leaves)
+                    .map(|message| Event::LeafRemoved {
+                        origin: origin.clone(),
+                        issue: issue.clone(),
+                        message: message.clone(),
+                    }),
+            );
+        }
+        events
+    }
+}
+
+impl<O> Default for Snapshot<O> {
+    fn default() -> Self {
+        Self {
+            issues: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::issue::Issue;
+    use crate::memory;
+    use crate::object::Database;
+    use crate::object::commit::Commit;
+
+    type TestRepo = memory::Repository;
+
+    #[test]
+    fn events() {
+        let repo: TestRepo = (
+            memory::Store::default().with_remotes(["origin".to_owned()]),
+            Default::default(),
+        );
+        let commit = |message: &str| {
+            repo.commit_builder(TestRepo::find_commit)
+                .expect("Cannot create commit builder")
+                .build(message)
+                .expect("Cannot create commit")
+                .id()
+        };
+        let set_remote = |path: String, target| {
+            repo.0
+                .set_reference(path.as_ref(), target, true, "")
+                .expect("Could not set reference")
+        };
+
+        let first = commit("First issue");
+        let second = commit("Second issue");
+        let reply = commit("Reply");
+        let issue = Issue::new_unchecked(&repo, first);
+        issue.update_head(first, false).expect("Could not set head");
+        set_remote(format!("refs/remotes/origin/dit/{second}/head"), second);
+
+        let before = Snapshot::capture(&repo).expect("Could not take snapshot");
+        assert_eq!(
+            before.get(&Origin::Local, &first),
+            Some(&IssueRefs {
+                head: Some(first),
+                leaves: Default::default(),
+            }),
+        );
+        assert_eq!(before.events(&before), []);
+
+        issue.add_leaf(reply).expect("Could not add leaf");
+        issue.update_head(reply, true).expect("Could not set head");
+        set_remote(format!("refs/remotes/origin/dit/{first}/head"), first);
+        set_remote(
+            format!("refs/remotes/origin/dit/{first}/leaves/{first}"),
+            first,
+        );
+        repo.0
+            .delete_reference(format!("refs/remotes/origin/dit/{second}/head").as_ref())
+            .expect("Could not delete reference");
+
+        let after = Snapshot::capture(&repo).expect("Could not take snapshot");
+        let remote = Origin::Remote("origin".to_owned());
+        let mut expected = vec![
+            Event::HeadMoved {
+                origin: Origin::Local,
+                issue: first,
+                old: Some(first),
+                new: Some(reply),
+            },
+            Event::NewMessage {
+                origin: Origin::Local,
+                issue: first,
+                message: reply,
+            },
+        ];
+        let mut remote_events = vec![
+            (
+                first,
+                vec![
+                    Event::NewIssue {
+                        origin: remote.clone(),
+                        issue: first,
+                    },
+                    Event::HeadMoved {
+                        origin: remote.clone(),
+                        issue: first,
+                        old: None,
+                        new: Some(first),
+                    },
+                    Event::NewMessage {
+                        origin: remote.clone(),
+                        issue: first,
+                        message: first,
+                    },
+                ],
+            ),
+            (
+                second,
+                vec![Event::IssueDisappeared {
+                    origin: remote.clone(),
+                    issue: second,
+                }],
+            ),
+        ];
+        remote_events.sort_by_key(|(id, _)| *id);
+        expected.extend(remote_events.into_iter().flat_map(|(_, e)| e));
+        assert_eq!(before.events(&after), expected);
+
+        let reverse = after.events(&before);
+        assert!(reverse.contains(&Event::LeafRemoved {
+            origin: Origin::Local,
+            issue: first,
+            message: reply,
+        }));
+        assert!(reverse.contains(&Event::IssueDisappeared {
+            origin: remote.clone(),
+            issue: first,
+        }));
+        assert!(reverse.contains(&Event::NewIssue {
+            origin: remote,
+            issue: second,
+        }));
+    }
+}
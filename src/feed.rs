@@ -0,0 +1,375 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Atom feeds
+//!
+//! This module provides the [Generator] for creating [Feed]s of the messages
+//! of a single issue or of all issues in a repository. Feeds are rendered as
+//! specified in RFC 4287 via their [fmt::Display] implementation.
+//!
+//! The ids of feeds and entries are URNs derived from the ids of issues and
+//! messages. A message will thus always be represented by an entry with the
+//! same id, regardless of the feed it appears in. Feeds may be generated
+//! incrementally by only including messages created after a given time.
+
+use std::fmt;
+
+use crate::error::{self, ResultExt};
+use crate::issue::Issue;
+use crate::message::Message;
+use crate::object::Database;
+use crate::object::commit::Commit;
+use crate::object::signature::Signature;
+use crate::repository::Repository;
+use crate::site::ISSUES_DIR;
+use crate::traversal::Traversible;
+use crate::utils::{DateTime, Escaped};
+
+/// Namespace of the Atom format
+pub const ATOM_NAMESPACE: &str = "http://www.w3.org/2005/Atom";
+
+/// Id of feeds of all issues in a repository
+pub const REPOSITORY_FEED_ID: &str = "urn:git-dit:issues";
+
+/// Retrieve the id of the feed of an issue
+pub fn issue_feed_id(issue: &impl fmt::Display) -> String {
+    format!("urn:git-dit:issue:{issue}")
+}
+
+/// Retrieve the id of the entry of a message
+pub fn entry_id(message: &impl fmt::Display) -> String {
+    format!("urn:git-dit:message:{message}")
+}
+
+/// Generator for feeds
+///
+/// Entries are created for messages in the order of the time at which they
+/// were committed, from the newest to the oldest one.
+pub struct Generator<'r, R> {
+    repo: &'r R,
+    since: Option<i64>,
+    link_base: Option<String>,
+}
+
+impl<'r, R> Generator<'r, R>
+where
+    R: Repository<'r> + Database<'r> + Traversible<'r>,
+{
+    /// Create a new generator for a repository
+    pub fn new(repo: &'r R) -> Self {
+        Self {
+            repo,
+            since: None,
+            link_base: None,
+        }
+    }
+
+    /// Only include messages committed after a given time
+    ///
+    /// The time is given in seconds since the Unix epoch. Usually, this will be
+    /// the [Feed::updated] time of a previously generated feed.
+    pub fn with_since(self, time: i64) -> Self {
+        Self {
+            since: Some(time),
+            ..self
+        }
+    }
+
+    /// Link entries to the pages of a [Site](crate::site::Site)
+    ///
+    /// The `url` is the location at which the site is published.
+    pub fn with_link_base(self, url: impl Into<String>) -> Self {
+        Self {
+            link_base: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Generate a feed of the messages of a single issue
+    ///
+    /// The feed's title is the subject of the issue's initial message.
+    pub fn issue_feed(&self, issue: &Issue<'r, R>) -> error::Result<Feed<R::Oid>, R::InnerError> {
+        let title = issue
+            .initial_message()?
+            .subject()
+            .wrap_with_kind(error::Kind::MalformedMessage)?
+            .to_owned();
+        let mut entries = Vec::new();
+        self.collect_entries(issue, &mut entries)?;
+        Ok(self.feed(issue_feed_id(issue.id()), title, entries))
+    }
+
+    /// Generate a feed of the messages of all issues in the repository
+    pub fn repository_feed(
+        &self,
+        title: impl Into<String>,
+    ) -> error::Result<Feed<R::Oid>, R::InnerError> {
+        let mut entries = Vec::new();
+        for issue in self.repo.issues()? {
+            self.collect_entries(&issue, &mut entries)?;
+        }
+        Ok(self.feed(REPOSITORY_FEED_ID.to_owned(), title.into(), entries))
+    }
+
+    /// Assemble a feed from a set of entries
+    fn feed(&self, id: String, title: String, mut entries: Vec<Entry<R::Oid>>) -> Feed<R::Oid> {
+        entries.sort_by(|a, b| b.updated.0.cmp(&a.updated.0).then_with(|| a.id.cmp(&b.id)));
+        let updated = entries
+            .first()
+            .map(|e| e.updated.0)
+            .or(self.since)
+            .unwrap_or_default();
+        Feed {
+            id,
+            title,
+            updated,
+            link: self.link_base.clone(),
+            entries,
+        }
+    }
+
+    /// Create entries for all messages of an issue within the time bound
+    fn collect_entries(
+        &self,
+        issue: &Issue<'r, R>,
+        entries: &mut Vec<Entry<R::Oid>>,
+    ) -> error::Result<(), R::InnerError> {
+        for message in issue.messages()? {
+            let message = message
+                .map_err(Into::into)
+                .wrap_with_kind(error::Kind::CannotGetCommit)?;
+            let message = self.repo.find_commit(message)?;
+            let committer = message.committer();
+            if self.since.is_some_and(|t| committer.time() <= t) {
+                continue;
+            }
+
+            let parts = message
+                .parts()
+                .wrap_with_kind(error::Kind::MalformedMessage)?;
+            let author = message.author();
+            let link = self.link_base.as_ref().map(|base| {
+                format!(
+                    "{}/{ISSUES_DIR}/{}.html#{}",
+                    base.trim_end_matches('/'),
+                    issue.id(),
+                    message.id(),
+                )
+            });
+            entries.push(Entry {
+                id: message.id(),
+                issue: issue.id().clone(),
+                title: parts.subject.to_owned(),
+                name: author
+                    .name()
+                    .wrap_with_kind(error::Kind::CannotGetSignature)?
+                    .to_owned(),
+                email: author
+                    .email()
+                    .wrap_with_kind(error::Kind::CannotGetSignature)?
+                    .to_owned(),
+                published: (author.time(), author.offset()),
+                updated: (committer.time(), committer.offset()),
+                content: parts.body.join("\n\n"),
+                link,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// An Atom feed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Feed<O> {
+    id: String,
+    title: String,
+    updated: i64,
+    link: Option<String>,
+    entries: Vec<Entry<O>>,
+}
+
+impl<O> Feed<O> {
+    /// Retrieve the id of the feed
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Retrieve the title of the feed
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Retrieve the time at which the feed was last updated
+    ///
+    /// This is the time at which the newest message included was committed. If
+    /// the feed is empty, the time bound used for generating it or the Unix
+    /// epoch is reported.
+    pub fn updated(&self) -> i64 {
+        self.updated
+    }
+
+    /// Retrieve the entries of the feed, starting with the newest one
+    pub fn entries(&self) -> &[Entry<O>] {
+        &self.entries
+    }
+}
+
+impl<O: fmt::Display> fmt::Display for Feed<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+        writeln!(f, "<feed xmlns=\"{ATOM_NAMESPACE}\">")?;
+        writeln!(f, "<id>{}</id>", Escaped(&self.id))?;
+        writeln!(f, "<title>{}</title>", Escaped(&self.title))?;
+        writeln!(
+            f,
+            "<updated>{}</updated>",
+            DateTime::new(self.updated, 0).rfc3339(),
+        )?;
+        if let Some(link) = self.link.as_ref() {
+            writeln!(f, "<link href=\"{}\"/>", Escaped(link))?;
+        }
+        self.entries.iter().try_for_each(|e| write!(f, "{e}"))?;
+        writeln!(f, "</feed>")
+    }
+}
+
+/// A single entry of a [Feed], representing a message
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry<O> {
+    /// Id of the message
+    pub id: O,
+    /// Id of the issue the message belongs to
+    pub issue: O,
+    /// Subject of the message
+    pub title: String,
+    /// Name of the message's author
+    pub name: String,
+    /// Email address of the message's author
+    pub email: String,
+    /// Time and offset at which the message was authored
+    pub published: (i64, i32),
+    /// Time and offset at which the message was committed
+    pub updated: (i64, i32),
+    /// Body of the message, excluding the subject and trailers
+    pub content: String,
+    /// Link to the message in a [Site](crate::site::Site)
+    pub link: Option<String>,
+}
+
+impl<O: fmt::Display> fmt::Display for Entry<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (published, published_offset) = self.published;
+        let (updated, updated_offset) = self.updated;
+        writeln!(f, "<entry>")?;
+        writeln!(f, "<id>{}</id>", entry_id(&self.id))?;
+        writeln!(f, "<title>{}</title>", Escaped(&self.title))?;
+        writeln!(f, "<author>")?;
+        writeln!(f, "<name>{}</name>", Escaped(&self.name))?;
+        writeln!(f, "<email>{}</email>", Escaped(&self.email))?;
+        writeln!(f, "</author>")?;
+        writeln!(
+            f,
+            "<published>{}</published>",
+            DateTime::new(published, published_offset).rfc3339(),
+        )?;
+        writeln!(
+            f,
+            "<updated>{}</updated>",
+            DateTime::new(updated, updated_offset).rfc3339(),
+        )?;
+        if let Some(link) = self.link.as_ref() {
+            writeln!(f, "<link href=\"{}\"/>", Escaped(link))?;
+        }
+        writeln!(
+            f,
+            "<content type=\"text\">{}</content>",
+            Escaped(&self.content),
+        )?;
+        writeln!(f, "</entry>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory;
+
+    type TestRepo = memory::Repository;
+
+    #[test]
+    fn generate() {
+        let repo = TestRepo::default();
+        let author = |time| memory::Signature::new("Foo", "foo@example.com").with_time(time, 60);
+
+        let first = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .with_author(author(10))
+            .with_committer(author(10))
+            .build("Crash\n\nIt <crashes>.")
+            .expect("Could not create issue");
+        let second = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .with_author(author(20))
+            .with_committer(author(20))
+            .build("Feature")
+            .expect("Could not create issue");
+        let reply = first
+            .message_builder()
+            .expect("Could not create builder")
+            .with_author(author(15))
+            .with_committer(author(30))
+            .with_parent(first.initial_message().expect("No initial message"))
+            .build("Re: Crash\n\nFixed.\n\nFor real.\n\nSigned-off-by: Foo")
+            .expect("Could not add message");
+
+        let generator = Generator::new(&repo).with_link_base("https://example.com/dit/");
+        let feed = generator
+            .repository_feed("All issues")
+            .expect("Could not generate feed");
+        assert_eq!(feed.updated(), 30);
+        assert_eq!(
+            feed.entries().iter().map(|e| e.id).collect::<Vec<_>>(),
+            [reply, *second.id(), *first.id()],
+        );
+        let entry = &feed.entries()[0];
+        assert_eq!(entry.issue, *first.id());
+        assert_eq!(entry.content, "Fixed.\n\nFor real.");
+        assert_eq!(entry.published, (15, 60));
+
+        let text = feed.to_string();
+        assert!(text.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed"));
+        assert!(text.contains("<updated>1970-01-01T00:00:30+00:00</updated>\n"));
+        assert!(text.contains(&format!("<id>urn:git-dit:message:{reply}</id>")));
+        assert!(text.contains("<published>1970-01-01T01:00:15+01:00</published>"));
+        assert!(text.contains(&format!(
+            "<link href=\"https://example.com/dit/issues/{}.html#{reply}\"/>",
+            first.id(),
+        )));
+        assert!(text.contains("<content type=\"text\">It &lt;crashes&gt;.</content>"));
+        assert!(text.ends_with("</entry>\n</feed>\n"));
+
+        let feed = Generator::new(&repo)
+            .with_since(20)
+            .issue_feed(&first)
+            .expect("Could not generate feed");
+        assert_eq!(feed.id(), issue_feed_id(first.id()));
+        assert_eq!(feed.title(), "Crash");
+        assert_eq!(
+            feed.entries().iter().map(|e| e.id).collect::<Vec<_>>(),
+            [reply],
+        );
+
+        let feed = Generator::new(&repo)
+            .with_since(30)
+            .issue_feed(&first)
+            .expect("Could not generate feed");
+        assert!(feed.entries().is_empty());
+        assert_eq!(feed.updated(), 30);
+    }
+}
@@ -15,18 +15,13 @@ use crate::object;
 use crate::reference;
 use crate::traversal::{TraversalBuilder, Traversible};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub enum ReferenceCollectionSpec {
+    #[default]
     Never,
     BackedByRemoteHead,
 }
 
-impl Default for ReferenceCollectionSpec {
-    fn default() -> Self {
-        Self::Never
-    }
-}
-
 /// Type representing collectable references
 ///
 /// Use this type in order to compute dit-references which are no longer
@@ -170,6 +165,61 @@ impl CollectableRefs {
             });
         Ok(std::iter::once(dead_leaves).chain(collectable).flatten())
     }
+
+    /// Delete all collectable references for an [Issue]
+    ///
+    /// Collectable references are determined via [Self::for_issue] and deleted
+    /// afterwards. Failing to delete an individual reference does not abort the
+    /// sweep. Instead, the failure is recorded in the returned [Sweep].
+    pub fn sweep<'r, R>(&self, issue: &Issue<'r, R>) -> error::Result<Sweep<'r, R>, R::InnerError>
+    where
+        R: reference::Store<'r> + object::Database<'r> + Traversible<'r>,
+    {
+        use reference::Reference;
+
+        let collectable: Vec<_> = self
+            .for_issue(issue)?
+            .collect::<Result<_, _>>()
+            .map_err(Into::into)
+            .wrap_with_kind(error::Kind::CannotGetReference)?;
+
+        let mut res = Sweep {
+            removed: Vec::new(),
+            failed: Vec::new(),
+        };
+        for reference in collectable {
+            let deleted = reference
+                .as_path()
+                .wrap_with_kind(error::Kind::ReferenceNameError)
+                .and_then(|p| issue.repo().delete_reference(p));
+            match deleted {
+                Ok(()) => res.removed.push(reference),
+                Err(e) => res.failed.push((reference, e)),
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// Report of a [CollectableRefs::sweep]
+pub struct Sweep<'r, R: reference::Store<'r>> {
+    /// References which were deleted
+    pub removed: Vec<R::Reference>,
+    /// References which could not be deleted, along with the respective error
+    pub failed: Vec<(R::Reference, error::Error<R::InnerError>)>,
+}
+
+impl<'r, R> std::fmt::Debug for Sweep<'r, R>
+where
+    R: reference::Store<'r>,
+    R::Reference: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sweep")
+            .field("removed", &self.removed)
+            .field("failed", &self.failed)
+            .finish()
+    }
 }
 
 type RefResult<'r, R> = Result<
@@ -181,13 +231,13 @@ type RefResult<'r, R> = Result<
 mod tests {
     use super::*;
 
-    use object::commit::Commit;
-    use object::tests::TestOdb;
+    use crate::memory;
+
     use object::Database;
-    use reference::tests::TestStore;
+    use object::commit::Commit;
     use reference::Reference;
 
-    type TestRepo = (TestStore, TestOdb);
+    type TestRepo = memory::Repository;
 
     #[test]
     fn collectable_leaves() {
@@ -286,4 +336,56 @@ mod tests {
         collected.sort();
         assert_eq!(refs_to_collect, collected);
     }
+
+    #[test]
+    fn sweep() {
+        use reference::Store;
+
+        let repo = TestRepo::default();
+
+        let initial_message = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .build("Test message 1")
+            .expect("Cannot create commit");
+        let issue = Issue::new_unchecked(&repo, initial_message.id());
+        issue
+            .update_head(initial_message.id(), true)
+            .expect("Could not update head");
+        let message1_id = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(initial_message.clone())
+            .build("Test message 2")
+            .expect("Could not add message");
+        let message1 = repo
+            .find_commit(message1_id)
+            .expect("Could not retrieve message");
+        let message2_id = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(message1)
+            .build("Test message 3")
+            .expect("Could not add message");
+
+        let sweep = CollectableRefs::default()
+            .sweep(&issue)
+            .expect("Could not sweep issue");
+        let removed: Vec<_> = sweep.removed.iter().filter_map(Reference::target).collect();
+        assert_eq!(removed, [message1_id]);
+        assert!(sweep.failed.is_empty());
+
+        let leaves: Vec<_> = issue
+            .local_refs()
+            .expect("Could not retrieve local refs")
+            .into_iter()
+            .map(|r| r.expect("Could not retrieve reference").target())
+            .collect();
+        assert_eq!(leaves.len(), 2);
+        assert!(leaves.contains(&Some(message2_id)));
+        assert!(!leaves.contains(&Some(message1_id)));
+
+        let path = format!("refs/dit/{}/leaves/{message1_id}", issue.id());
+        assert!(repo.delete_reference(path.as_ref()).is_err());
+    }
 }
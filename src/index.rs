@@ -0,0 +1,260 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Lookup indices
+//!
+//! This module provides the [MessageIndex] for quickly finding the issue a
+//! message belongs to.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::path::PathBuf;
+
+use crate::error::{self, ResultExt};
+use crate::issue::{DIT_REF_PART, Issue};
+use crate::object::Database;
+use crate::reference::{Reference, Store};
+use crate::traversal::Traversible;
+
+/// Index mapping messages to issues
+///
+/// The index is built from the local and remote head and leaf references of
+/// all issues. It maps every message reachable from one of those references
+/// via first parents to the issue the reference belongs to.
+///
+/// An index is populated and kept up to date via [MessageIndex::update]. Only
+/// references which are new or changed since the last update are considered
+/// during an update. The traversal of the messages of such a reference stops
+/// at the first message already indexed. If a reference was removed or moved,
+/// all messages of the affected issue are dropped and indexed anew from the
+/// issue's current references.
+#[derive(Clone, Debug)]
+pub struct MessageIndex<O> {
+    messages: HashMap<O, O>,
+    refs: HashMap<PathBuf, O>,
+}
+
+impl<O: Clone + Eq + Hash> MessageIndex<O> {
+    /// Create a new, empty index
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Retrieve the id of the issue a message belongs to
+    pub fn get(&self, message: &O) -> Option<&O> {
+        self.messages.get(message)
+    }
+
+    /// Retrieve the number of messages indexed
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Check whether no messages are indexed
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Update the index from the references in a repository
+    ///
+    /// Indexes the messages reachable from all references which were not
+    /// considered in a previous update, or whose target changed since. Messages
+    /// no longer reachable from any reference are removed. Returns the number
+    /// of messages added to the index, including messages indexed anew.
+    pub fn update<'r, R>(&mut self, repo: &'r R) -> error::Result<usize, R::InnerError>
+    where
+        R: Store<'r, Oid = O> + Database<'r> + Traversible<'r>,
+    {
+        let mut prefixes = vec!["refs".to_owned()];
+        prefixes.extend(repo.remote_ref_paths()?);
+
+        let mut references = Vec::new();
+        for prefix in prefixes {
+            let path = format!("{prefix}/{DIT_REF_PART}");
+            for reference in repo.references(path.as_ref())? {
+                references.push(reference.wrap_with_kind(error::Kind::CannotGetReference)?);
+            }
+        }
+
+        let current: HashMap<_, _> = references
+            .iter()
+            .filter_map(|r| Some((r.as_path().ok()?, r.target()?)))
+            .collect();
+        self.purge(|path, target| current.get(path.as_path()) == Some(target));
+
+        references.iter().map(|r| self.add_reference(repo, r)).sum()
+    }
+
+    /// Remove the messages of all issues with references which are now stale
+    ///
+    /// The predicate reports whether a previously indexed reference and its
+    /// target are still current.
+    fn purge(&mut self, current: impl Fn(&PathBuf, &O) -> bool) {
+        let Self { messages, refs } = self;
+        let stale: HashSet<_> = refs
+            .iter()
+            .filter(|(path, target)| !current(path, target))
+            .filter_map(|(_, target)| messages.get(target).cloned())
+            .collect();
+        if stale.is_empty() {
+            return;
+        }
+
+        refs.retain(|path, target| {
+            current(path, target) && messages.get(target).is_some_and(|i| !stale.contains(i))
+        });
+        messages.retain(|_, issue| !stale.contains(issue));
+    }
+
+    /// Index the messages reachable from a single reference
+    ///
+    /// Returns the number of messages added to the index.
+    fn add_reference<'r, R>(
+        &mut self,
+        repo: &'r R,
+        reference: &R::Reference,
+    ) -> error::Result<usize, R::InnerError>
+    where
+        R: Store<'r, Oid = O> + Database<'r> + Traversible<'r>,
+    {
+        let (Some(parts), Some(target)) = (reference.parts(), reference.target()) else {
+            return Ok(0);
+        };
+        let Ok(path) = reference.as_path() else {
+            return Ok(0);
+        };
+        if self.refs.get(path) == Some(&target) {
+            return Ok(0);
+        }
+
+        let issue = Issue::new_unchecked(repo, parts.issue);
+        let mut added = 0;
+        for message in issue.messages_from(target.clone())? {
+            let message = message
+                .map_err(Into::into)
+                .wrap_with_kind(error::Kind::CannotGetCommit)?;
+            if self.messages.contains_key(&message) {
+                break;
+            }
+            self.messages.insert(message, issue.id().clone());
+            added += 1;
+        }
+        self.refs.insert(path.to_owned(), target);
+        Ok(added)
+    }
+}
+
+impl<O> Default for MessageIndex<O> {
+    fn default() -> Self {
+        Self {
+            messages: Default::default(),
+            refs: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory;
+    use crate::object::commit::Commit;
+    use crate::repository::Repository;
+
+    type TestRepo = memory::Repository;
+
+    #[test]
+    fn incremental_update() {
+        let repo = TestRepo::default();
+
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test message 1")
+            .expect("Could not create issue");
+        let reply = |parent| {
+            issue
+                .message_builder()
+                .expect("Could not create builder")
+                .with_parent(repo.find_commit(parent).expect("No such message"))
+                .build("Reply")
+                .expect("Could not add message")
+        };
+        let first = reply(*issue.id());
+
+        let mut index = MessageIndex::new();
+        assert_eq!(index.update(&repo).expect("Could not update index"), 2);
+        assert_eq!(index.get(issue.id()), Some(issue.id()));
+        assert_eq!(index.get(&first), Some(issue.id()));
+        assert_eq!(index.update(&repo).expect("Could not update index"), 0);
+
+        let second = reply(first);
+        let other = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test message 2")
+            .expect("Could not create issue");
+        assert_eq!(index.get(&second), None);
+        assert_eq!(index.update(&repo).expect("Could not update index"), 2);
+        assert_eq!(index.get(&second), Some(issue.id()));
+        assert_eq!(index.get(other.id()), Some(other.id()));
+
+        let stray = repo
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .build("Stray")
+            .expect("Cannot create commit")
+            .id();
+        index.update(&repo).expect("Could not update index");
+        assert_eq!(index.get(&stray), None);
+        assert_eq!(index.len(), 4);
+    }
+
+    #[test]
+    fn stale_references() {
+        use crate::reference::Store;
+
+        let repo = TestRepo::default();
+
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test message")
+            .expect("Could not create issue");
+        let first = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(repo.find_commit(*issue.id()).expect("No such message"))
+            .build("Reply")
+            .expect("Could not add message");
+        let second = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(repo.find_commit(first).expect("No such message"))
+            .build("Reply")
+            .expect("Could not add message");
+
+        let mut index = MessageIndex::new();
+        assert_eq!(index.update(&repo).expect("Could not update index"), 3);
+
+        let leaf = format!("refs/dit/{}/leaves/{second}", issue.id());
+        repo.delete_reference(leaf.as_ref())
+            .expect("Could not delete reference");
+        index.update(&repo).expect("Could not update index");
+        assert_eq!(index.get(&second), None);
+        assert_eq!(index.get(&first), Some(issue.id()));
+        assert_eq!(index.get(issue.id()), Some(issue.id()));
+        assert_eq!(index.len(), 2);
+
+        let leaf = format!("refs/dit/{}/leaves/{first}", issue.id());
+        repo.delete_reference(leaf.as_ref())
+            .expect("Could not delete reference");
+        index.update(&repo).expect("Could not update index");
+        assert_eq!(index.get(&first), None);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.update(&repo).expect("Could not update index"), 0);
+    }
+}
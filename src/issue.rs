@@ -9,14 +9,19 @@
 //!
 //! This module provides the `Issue` type and related functionality.
 
+pub mod head;
+pub mod thread;
+
 use std::fmt::{self, Write};
 use std::hash;
 
 use crate::base::Base;
 use crate::error::{self, ResultExt};
-use crate::object::{commit, Database};
+use crate::object::{Database, commit};
 use crate::reference::{self, HEAD_COMPONENT};
 use crate::remote;
+use crate::trailer::accumulation::{Accumulator, MultiAccumulator};
+use crate::trailer::spec::ToMap;
 use crate::traversal::{TraversalBuilder, Traversible};
 
 /// Issue handle
@@ -188,6 +193,24 @@ impl<'r, R: reference::Store<'r>> Issue<'r, R> {
             .set_reference(path.as_ref(), message, replace, &reflogmsg)
     }
 
+    /// Advance the local head reference of the issue
+    ///
+    /// Updates the local head reference of the issue to the provided message,
+    /// but only if it currently points to `expected`. If `expected` is `None`,
+    /// the head reference is only created if it does not exist yet. Unlike
+    /// [Self::update_head], this fn thus never discards a head set
+    /// concurrently.
+    pub fn advance_head(
+        &self,
+        message: R::Oid,
+        expected: Option<R::Oid>,
+    ) -> error::Result<R::Reference, R::InnerError> {
+        let path = format!("refs/{DIT_REF_PART}/{}/{HEAD_COMPONENT}", self.id());
+        let reflogmsg = format!("git-dit: advance head reference of {self} to {message}");
+        self.repo()
+            .set_reference_matching(path.as_ref(), message, expected, &reflogmsg)
+    }
+
     /// Add a new leaf reference associated with the issue
     ///
     /// Creates a new leaf reference for the message provided in the issue.
@@ -228,6 +251,38 @@ impl<'r, R: Database<'r>> Issue<'r, R> {
             .commit_builder(move |_, o: R::Oid| self.add_leaf(o.clone()).map(|_| o))
     }
 
+    /// Create a [commit::Builder] for status messages for this issue
+    ///
+    /// The builder will be configured with the current local head of the issue
+    /// as the first parent or, if there is no local head, with the initial
+    /// message. A discussion message the status change refers to may be added
+    /// as the second parent via [commit::Builder::with_parent].
+    ///
+    /// After creating the new message, the local head will be advanced to it
+    /// via [Self::advance_head]. Thus, building the message will fail if the
+    /// head was moved in the meantime.
+    pub fn head_builder<'c>(
+        &self,
+    ) -> error::Result<
+        commit::Builder<'r, 'c, R, impl commit::FollowUp<'r, R, Output = R::Oid> + '_>,
+        R::InnerError,
+    >
+    where
+        R: reference::Store<'r>,
+        'r: 'c,
+    {
+        use reference::Reference;
+
+        let current = self.local_head()?.and_then(|r| r.target());
+        let parent = self
+            .repo()
+            .find_commit(current.clone().unwrap_or_else(|| self.id().clone()))?;
+        let builder = self
+            .repo()
+            .commit_builder(move |_, o: R::Oid| self.advance_head(o.clone(), current).map(|_| o))?;
+        Ok(builder.with_parent(parent))
+    }
+
     /// Add a new message to the issue
     ///
     /// Adds a new message to the issue. Also create a leaf reference for the
@@ -281,6 +336,24 @@ impl<'r, R: Database<'r> + Traversible<'r>> Issue<'r, R> {
             .wrap_with_kind(error::Kind::CannotConstructRevwalk)
     }
 
+    /// Get the reply tree of the issue
+    ///
+    /// The [thread::Thread] is built from all messages reachable from the
+    /// issue's local and remote references.
+    pub fn thread(&self) -> error::Result<thread::Thread<R::Oid>, R::InnerError>
+    where
+        R: reference::Store<'r>,
+    {
+        self.messages()?
+            .map(|id| {
+                let id = id
+                    .map_err(Into::into)
+                    .wrap_with_kind(error::Kind::CannotGetCommit)?;
+                self.repo().find_commit(id)
+            })
+            .collect()
+    }
+
     /// Get messages of the issue starting from a specific one
     ///
     /// The [Iterator] returned will return all first parents up to and
@@ -296,6 +369,172 @@ impl<'r, R: Database<'r> + Traversible<'r>> Issue<'r, R> {
             .wrap_with_kind(error::Kind::CannotConstructRevwalk)
     }
 
+    /// Analyse how the remote heads of the issue relate to its local head
+    ///
+    /// Each remote head is classified as a [head::Relation] relative to the
+    /// local head. In addition, the [head::Analysis] will contain the message
+    /// the local head may safely be fast-forwarded to, if any.
+    pub fn analyse_heads(&self) -> error::Result<head::Analysis<'r, R>, R::InnerError>
+    where
+        R: reference::Store<'r>,
+    {
+        use head::Relation;
+        use reference::Reference;
+
+        let local = self.local_head()?.and_then(|r| r.target());
+        let remotes = self
+            .all_remote_heads()?
+            .filter_map(|r| r.map(|r| r.target().map(|t| (r, t))).transpose())
+            .map(|r| {
+                let (reference, target) = r?;
+                let relation = match local.as_ref() {
+                    None => Relation::Ahead,
+                    Some(local) if *local == target => Relation::Equal,
+                    Some(local) if self.is_ancestor(local, target.clone())? => Relation::Ahead,
+                    Some(local) if self.is_ancestor(&target, local.clone())? => Relation::Behind,
+                    Some(_) => Relation::Diverged,
+                };
+                Ok(head::RemoteHead {
+                    reference,
+                    target,
+                    relation,
+                })
+            })
+            .collect::<error::Result<Vec<_>, _>>()?;
+
+        let ahead: Vec<_> = remotes
+            .iter()
+            .filter(|r| r.relation == Relation::Ahead)
+            .map(|r| &r.target)
+            .collect();
+        let mut fast_forward = None;
+        'candidates: for candidate in &ahead {
+            for other in &ahead {
+                if other != candidate && !self.is_ancestor(other, (*candidate).clone())? {
+                    continue 'candidates;
+                }
+            }
+            fast_forward = Some((*candidate).clone());
+            break;
+        }
+
+        Ok(head::Analysis::new(local, remotes, fast_forward))
+    }
+
+    /// Fast-forward the local head of the issue
+    ///
+    /// Advances the local head to the [head::Analysis::fast_forward_target]
+    /// if there is one. Returns the updated reference or `None` if no
+    /// fast-forward was possible.
+    pub fn fast_forward(&self) -> error::Result<Option<R::Reference>, R::InnerError>
+    where
+        R: reference::Store<'r>,
+    {
+        let analysis = self.analyse_heads()?;
+        analysis
+            .fast_forward_target()
+            .map(|t| self.advance_head(t.clone(), analysis.local().cloned()))
+            .transpose()
+    }
+
+    /// Check whether a message is a first parent ancestor of another one
+    ///
+    /// Messages are considered ancestors of themselves.
+    fn is_ancestor(
+        &self,
+        ancestor: &R::Oid,
+        message: R::Oid,
+    ) -> error::Result<bool, R::InnerError> {
+        for id in self.messages_from(message)? {
+            let id = id
+                .map_err(Into::into)
+                .wrap_with_kind(error::Kind::CannotGetCommit)?;
+            if id == *ancestor {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Accumulate metadata from the local head of the issue
+    ///
+    /// Metadata is accumulated from the messages reachable from the issue's
+    /// local head via first parents, according to the `specs` provided. The
+    /// resulting map may be used with
+    /// [TrailerFilter::matches](crate::trailer::filter::TrailerFilter::matches).
+    pub fn metadata<S: ToMap>(&self, specs: S) -> error::Result<S::Output, R::InnerError>
+    where
+        R: reference::Store<'r>,
+        S::Output: MultiAccumulator,
+    {
+        let head = self.local_head()?;
+        self.metadata_from_head(head, specs)
+    }
+
+    /// Accumulate metadata from the head of the issue for a specific remote
+    ///
+    /// This fn behaves like [Self::metadata], but starts from the head
+    /// reference of a specific remote repository.
+    pub fn remote_metadata<S: ToMap>(
+        &self,
+        remote: &impl remote::Name,
+        specs: S,
+    ) -> error::Result<S::Output, R::InnerError>
+    where
+        R: reference::Store<'r>,
+        S::Output: MultiAccumulator,
+    {
+        let head = self.remote_head(remote)?;
+        self.metadata_from_head(head, specs)
+    }
+
+    /// Accumulate metadata starting from a specific message
+    ///
+    /// Trailers are processed from the newest to the oldest message, following
+    /// first parents up to and including the initial message of the issue.
+    pub fn metadata_from<S: ToMap>(
+        &self,
+        message: R::Oid,
+        specs: S,
+    ) -> error::Result<S::Output, R::InnerError>
+    where
+        S::Output: MultiAccumulator,
+    {
+        use crate::message::Message;
+
+        let mut metadata = specs.into_map();
+        for id in self.messages_from(message)? {
+            let id = id
+                .map_err(Into::into)
+                .wrap_with_kind(error::Kind::CannotGetCommit)?;
+            let trailers = self
+                .repo()
+                .find_commit(id)?
+                .trailers()
+                .wrap_with_kind(error::Kind::MalformedMessage)?;
+            metadata.process_all(trailers.rev());
+        }
+        Ok(metadata)
+    }
+
+    /// Accumulate metadata from a given head reference
+    fn metadata_from_head<S: ToMap>(
+        &self,
+        head: Option<R::Reference>,
+        specs: S,
+    ) -> error::Result<S::Output, R::InnerError>
+    where
+        R: reference::Store<'r>,
+        S::Output: MultiAccumulator,
+    {
+        use reference::Reference;
+
+        let head = head
+            .and_then(|r| r.target())
+            .ok_or_else(|| error::Kind::CannotFindIssueHead(self.id().clone()))?;
+        self.metadata_from(head, specs)
+    }
+
     /// Prepare a messages iterator which will terminate at the initial message
     pub fn terminated_messages(&self) -> error::Result<R::TraversalBuilder, R::InnerError> {
         use commit::Commit;
@@ -338,13 +577,14 @@ pub(crate) const DIT_REF_PART: &str = "dit";
 mod tests {
     use super::*;
 
-    use crate::object::tests::TestOdb;
+    use crate::memory;
 
     use commit::Commit;
-    use reference::tests::TestStore;
-    use reference::Reference;
+    use reference::{Reference, Store};
+
+    use crate::trailer::accumulation::ValueAccumulator;
 
-    type TestRepo = (TestStore, TestOdb);
+    type TestRepo = memory::Repository;
 
     #[test]
     fn issue_leaves() {
@@ -434,7 +674,7 @@ mod tests {
             .build("Test message 3")
             .expect("Could not add message");
 
-        let mut ids = vec![issue.id().clone(), message];
+        let mut ids = vec![*issue.id(), message];
         ids.sort();
         let mut ref_ids: Vec<_> = issue
             .local_refs()
@@ -505,6 +745,51 @@ mod tests {
         assert_eq!(iter2.next(), None);
     }
 
+    #[test]
+    fn thread() {
+        let repo = TestRepo::default();
+        let author = |time| memory::Signature::new("Foo", "foo@example.com").with_time(time, 0);
+
+        let initial_message = repo
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .with_author(author(1))
+            .build("Test message 1")
+            .expect("Cannot create commit");
+        let issue = Issue::new_unchecked(&repo, initial_message.id());
+        issue
+            .update_head(initial_message.id(), true)
+            .expect("Could not update head");
+        let reply = |parent, time| {
+            let parent = repo.find_commit(parent).expect("Could not find parent");
+            issue
+                .message_builder()
+                .expect("Could not create builder")
+                .with_author(author(time))
+                .with_parent(parent)
+                .build("Reply")
+                .expect("Could not add message")
+        };
+        let late = reply(*issue.id(), 3);
+        let early = reply(*issue.id(), 2);
+        let nested = reply(late, 4);
+
+        let thread = issue.thread().expect("Could not build thread");
+        let nodes: Vec<_> = thread.iter().map(|n| (*n.id(), n.depth())).collect();
+        assert_eq!(
+            nodes,
+            [(*issue.id(), 0), (early, 1), (late, 1), (nested, 2)]
+        );
+
+        let roots: Vec<_> = thread.roots().map(|n| *n.id()).collect();
+        assert_eq!(roots, [*issue.id()]);
+
+        let late = thread.get(&late).expect("Could not find message");
+        let children: Vec<_> = thread.children(late).map(|n| *n.id()).collect();
+        assert_eq!(children, [nested]);
+        assert_eq!(thread.parent(late).map(|n| *n.id()), Some(*issue.id()));
+    }
+
     #[test]
     fn update_head() {
         let repo = TestRepo::default();
@@ -545,4 +830,229 @@ mod tests {
             .expect("Could not get target of local head");
         assert_eq!(local_head, message);
     }
+
+    #[test]
+    fn head_builder() {
+        use crate::message::Composer;
+        use crate::trailer::Trailer;
+
+        let repo = TestRepo::default();
+
+        let initial_message = repo
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .build("Test message 1")
+            .expect("Cannot create commit");
+        let issue = Issue::new_unchecked(&repo, initial_message.id());
+        let discussion = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(initial_message.clone())
+            .build("Reply")
+            .expect("Could not add message");
+
+        let status = issue
+            .head_builder()
+            .expect("Could not create builder")
+            .with_parent(repo.find_commit(discussion).expect("No such message"))
+            .build(Composer::new("Close").with_trailer(Trailer::new("Dit-status", "closed")))
+            .expect("Could not add status message");
+        let parents: Vec<_> = repo
+            .find_commit(status)
+            .expect("No such message")
+            .parent_ids()
+            .into_iter()
+            .collect();
+        assert_eq!(parents, [*issue.id(), discussion]);
+
+        let head = |issue: &Issue<TestRepo>| {
+            issue
+                .local_head()
+                .expect("Could not retrieve local head")
+                .and_then(|r| r.target())
+        };
+        assert_eq!(head(&issue), Some(status));
+
+        let next = issue.head_builder().expect("Could not create builder");
+        issue
+            .advance_head(discussion, Some(status))
+            .expect("Could not advance head");
+        assert!(next.build("Reopen").is_err());
+        assert_eq!(head(&issue), Some(discussion));
+
+        assert!(issue.advance_head(status, None).is_err());
+        assert!(issue.advance_head(status, Some(status)).is_err());
+        assert_eq!(head(&issue), Some(discussion));
+    }
+
+    #[test]
+    fn analyse_heads() {
+        use head::Relation;
+
+        let repo: TestRepo = (
+            memory::Store::default().with_remotes(["a".to_owned(), "b".to_owned()]),
+            Default::default(),
+        );
+        let message = |parent: Option<memory::Oid>| {
+            repo.commit_builder(TestRepo::find_commit)
+                .expect("Cannot create commit builder")
+                .with_parents(parent.map(|p| repo.find_commit(p).expect("No such message")))
+                .build("Test message")
+                .expect("Cannot create commit")
+                .id()
+        };
+        let initial = message(None);
+        let set_remote = |remote: &str, target| {
+            let path = format!("refs/remotes/{remote}/dit/{initial}/head");
+            repo.0
+                .set_reference(path.as_ref(), target, true, "")
+                .expect("Could not set remote head")
+        };
+        let relations = |issue: &Issue<TestRepo>| {
+            let analysis = issue.analyse_heads().expect("Could not analyse heads");
+            let relations: Vec<_> = analysis.remotes().iter().map(|r| r.relation).collect();
+            (relations, analysis.fast_forward_target().cloned())
+        };
+
+        let issue = Issue::new_unchecked(&repo, initial);
+        let first = message(Some(initial));
+        let second = message(Some(first));
+        let other = message(Some(initial));
+
+        set_remote("a", second);
+        set_remote("b", first);
+        assert_eq!(
+            relations(&issue),
+            (vec![Relation::Ahead, Relation::Ahead], Some(second))
+        );
+
+        issue.advance_head(first, None).expect("Could not set head");
+        set_remote("b", initial);
+        assert_eq!(
+            relations(&issue),
+            (vec![Relation::Ahead, Relation::Behind], Some(second))
+        );
+
+        set_remote("b", other);
+        let analysis = issue.analyse_heads().expect("Could not analyse heads");
+        assert!(analysis.is_diverged());
+        assert_eq!(analysis.local(), Some(&first));
+
+        let head = issue
+            .fast_forward()
+            .expect("Could not fast-forward")
+            .and_then(|r| r.target());
+        assert_eq!(head, Some(second));
+        assert_eq!(
+            relations(&issue),
+            (vec![Relation::Equal, Relation::Diverged], None)
+        );
+
+        set_remote("a", message(Some(second)));
+        set_remote("b", message(Some(second)));
+        assert_eq!(
+            relations(&issue),
+            (vec![Relation::Ahead, Relation::Ahead], None)
+        );
+        assert!(
+            issue
+                .fast_forward()
+                .expect("Could not fast-forward")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        use crate::trailer::TrailerValue;
+        use crate::trailer::accumulation::AccumulationPolicy;
+        use crate::trailer::spec::{ISSUE_STATUS_SPEC, ISSUE_TYPE_SPEC, TrailerSpec};
+
+        let repo = TestRepo::default();
+        let initial_message = repo
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .build("Test message 1\n\nDit-type: bug\nDit-status: open\nFoo-bar: 1")
+            .expect("Cannot create commit");
+        let issue = Issue::new_unchecked(&repo, initial_message.id());
+        let specs = [
+            ISSUE_STATUS_SPEC,
+            ISSUE_TYPE_SPEC,
+            TrailerSpec {
+                key: "Foo-bar".into(),
+                accumulation: AccumulationPolicy::List,
+            },
+        ];
+        assert!(issue.metadata(&specs).is_err());
+
+        issue
+            .update_head(initial_message.id(), true)
+            .expect("Could not update head");
+        let message = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(initial_message)
+            .build("Test message 2\n\nDit-status: closed\nFoo-bar: 2\nFoo-bar: 3")
+            .expect("Could not add message");
+
+        let values = |metadata: &std::collections::HashMap<_, ValueAccumulator>, key| {
+            metadata
+                .get(key)
+                .cloned()
+                .expect("Could not find value")
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        let metadata = issue
+            .metadata(&specs)
+            .expect("Could not accumulate metadata");
+        assert_eq!(
+            values(&metadata, "Dit-status"),
+            [TrailerValue::from_slice("open")]
+        );
+        assert_eq!(
+            values(&metadata, "Dit-type"),
+            [TrailerValue::from_slice("bug")]
+        );
+        assert_eq!(values(&metadata, "Foo-bar"), [TrailerValue::Int(1)]);
+
+        let metadata = issue
+            .metadata_from(message, &specs)
+            .expect("Could not accumulate metadata");
+        assert_eq!(
+            values(&metadata, "Dit-status"),
+            [TrailerValue::from_slice("closed")]
+        );
+        assert_eq!(
+            values(&metadata, "Dit-type"),
+            [TrailerValue::from_slice("bug")]
+        );
+        assert_eq!(
+            values(&metadata, "Foo-bar"),
+            [
+                TrailerValue::Int(3),
+                TrailerValue::Int(2),
+                TrailerValue::Int(1)
+            ]
+        );
+
+        let remote: &[u8] = b"origin";
+        assert!(issue.remote_metadata(&remote, &specs).is_err());
+        repo.0
+            .set_reference(
+                format!("refs/remotes/origin/dit/{}/head", issue.id()).as_ref(),
+                message,
+                false,
+                "",
+            )
+            .expect("Could not set remote head");
+        let metadata = issue
+            .remote_metadata(&remote, &specs)
+            .expect("Could not accumulate metadata");
+        assert_eq!(
+            values(&metadata, "Dit-status"),
+            [TrailerValue::from_slice("closed")]
+        );
+    }
 }
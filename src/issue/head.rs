@@ -0,0 +1,92 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Head analysis
+//!
+//! This module provides the [Analysis] type describing how the remote heads of
+//! an issue relate to its local head. Heads are compared by following first
+//! parents, i.e. along the chain of messages from which metadata is
+//! accumulated.
+
+use crate::reference::Store;
+
+/// Relation of a remote head to the local head of an issue
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Relation {
+    /// The remote head refers to the same message as the local head
+    Equal,
+    /// The remote head is a descendant of the local head
+    ///
+    /// This is also the case if there is no local head.
+    Ahead,
+    /// The remote head is an ancestor of the local head
+    Behind,
+    /// Neither head is an ancestor of the other
+    Diverged,
+}
+
+/// A remote head of an issue
+pub struct RemoteHead<'r, R: Store<'r>> {
+    /// The remote head reference
+    pub reference: R::Reference,
+    /// The message the reference points to
+    pub target: R::Oid,
+    /// The relation to the local head
+    pub relation: Relation,
+}
+
+/// Analysis of an issue's heads
+///
+/// Use [Issue::analyse_heads](super::Issue::analyse_heads) for creating an
+/// analysis.
+pub struct Analysis<'r, R: Store<'r>> {
+    local: Option<R::Oid>,
+    remotes: Vec<RemoteHead<'r, R>>,
+    fast_forward: Option<R::Oid>,
+}
+
+impl<'r, R: Store<'r>> Analysis<'r, R> {
+    /// Create a new analysis
+    pub(super) fn new(
+        local: Option<R::Oid>,
+        remotes: Vec<RemoteHead<'r, R>>,
+        fast_forward: Option<R::Oid>,
+    ) -> Self {
+        Self {
+            local,
+            remotes,
+            fast_forward,
+        }
+    }
+
+    /// Retrieve the message the local head points to
+    pub fn local(&self) -> Option<&R::Oid> {
+        self.local.as_ref()
+    }
+
+    /// Retrieve all remote heads
+    pub fn remotes(&self) -> &[RemoteHead<'r, R>] {
+        self.remotes.as_ref()
+    }
+
+    /// Check whether any remote head diverged from the local head
+    pub fn is_diverged(&self) -> bool {
+        self.remotes
+            .iter()
+            .any(|r| r.relation == Relation::Diverged)
+    }
+
+    /// Retrieve the message the local head may be fast-forwarded to
+    ///
+    /// A fast-forward is considered safe if the message is a descendant of the
+    /// local head and of all other remote heads which are [Relation::Ahead].
+    /// Thus, no message from any remote head ahead of the local head is lost
+    /// by fast-forwarding. This fn returns `None` if no remote head is ahead of
+    /// the local head or if those heads diverged from one another.
+    pub fn fast_forward_target(&self) -> Option<&R::Oid> {
+        self.fast_forward.as_ref()
+    }
+}
@@ -0,0 +1,170 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Reply trees
+//!
+//! This module provides the [Thread] type representing the discussion of an
+//! issue as a tree of messages, in which each message is a child of the message
+//! it replies to, i.e. its first parent.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::slice;
+
+use crate::object::commit::Commit;
+use crate::object::signature::Signature;
+
+/// Reply tree of messages
+///
+/// Messages whose first parent is not part of the tree, e.g. the initial
+/// message of an issue, are roots of the tree. The children of each message
+/// are ordered by their author time and, for equal times, by their id.
+///
+/// The [Node]s of a thread are stored in pre-order, i.e. each message is
+/// followed by all its (indirect) replies before the next sibling. Iterating
+/// over a thread thus yields messages in the order suitable for rendering an
+/// indented discussion.
+#[derive(Clone, Debug)]
+pub struct Thread<O> {
+    nodes: Vec<Node<O>>,
+    roots: Vec<usize>,
+    index: HashMap<O, usize>,
+}
+
+impl<O: Eq + Hash> Thread<O> {
+    /// Retrieve the node for a given message
+    pub fn get(&self, id: &O) -> Option<&Node<O>> {
+        self.index.get(id).map(|i| &self.nodes[*i])
+    }
+}
+
+impl<O> Thread<O> {
+    /// Iterate over all nodes in pre-order
+    pub fn iter(&self) -> slice::Iter<'_, Node<O>> {
+        self.nodes.iter()
+    }
+
+    /// Iterate over the root nodes
+    pub fn roots(&self) -> impl Iterator<Item = &Node<O>> + '_ {
+        self.roots.iter().map(|i| &self.nodes[*i])
+    }
+
+    /// Iterate over the direct children of a node
+    pub fn children<'a>(&'a self, node: &'a Node<O>) -> impl Iterator<Item = &'a Node<O>> + 'a {
+        node.children.iter().map(|i| &self.nodes[*i])
+    }
+
+    /// Retrieve the parent node of a node
+    pub fn parent(&self, node: &Node<O>) -> Option<&Node<O>> {
+        node.parent.map(|i| &self.nodes[i])
+    }
+
+    /// Retrieve the number of messages in this thread
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check whether this thread is empty
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<O, C> FromIterator<C> for Thread<O>
+where
+    O: Clone + Ord + Hash,
+    C: Commit<Oid = O>,
+{
+    fn from_iter<I: IntoIterator<Item = C>>(iter: I) -> Self {
+        let messages: HashMap<_, _> = iter
+            .into_iter()
+            .map(|c| {
+                let parent = c.parent_ids().into_iter().next();
+                (c.id(), (parent, c.author().time()))
+            })
+            .collect();
+
+        let mut roots = Vec::new();
+        let mut children: HashMap<_, Vec<_>> = HashMap::new();
+        for (id, (parent, time)) in &messages {
+            match parent.as_ref().filter(|p| messages.contains_key(p)) {
+                Some(parent) => children.entry(parent).or_default().push((*time, id)),
+                None => roots.push((*time, id)),
+            }
+        }
+        roots.sort_unstable();
+        children.values_mut().for_each(|c| c.sort_unstable());
+
+        let mut thread = Thread {
+            nodes: Vec::with_capacity(messages.len()),
+            roots: Vec::new(),
+            index: HashMap::with_capacity(messages.len()),
+        };
+        let mut stack: Vec<_> = roots.into_iter().rev().map(|(_, id)| (id, None)).collect();
+        while let Some((id, parent)) = stack.pop() {
+            let index = thread.nodes.len();
+            let depth = match parent {
+                Some(p) => {
+                    let parent: &mut Node<O> = &mut thread.nodes[p];
+                    parent.children.push(index);
+                    parent.depth + 1
+                }
+                None => {
+                    thread.roots.push(index);
+                    0
+                }
+            };
+            thread.nodes.push(Node {
+                id: id.clone(),
+                depth,
+                parent,
+                children: Vec::new(),
+            });
+            thread.index.insert(id.clone(), index);
+
+            let replies = children.remove(id).unwrap_or_default();
+            stack.extend(replies.into_iter().rev().map(|(_, c)| (c, Some(index))));
+        }
+        thread
+    }
+}
+
+impl<'a, O> IntoIterator for &'a Thread<O> {
+    type Item = &'a Node<O>;
+    type IntoIter = slice::Iter<'a, Node<O>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A single message in a [Thread]
+#[derive(Clone, Debug)]
+pub struct Node<O> {
+    id: O,
+    depth: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+impl<O> Node<O> {
+    /// Retrieve the id of the message
+    pub fn id(&self) -> &O {
+        &self.id
+    }
+
+    /// Retrieve the depth of the message in the tree
+    ///
+    /// Roots have a depth of zero.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Retrieve the number of direct replies to this message
+    pub fn reply_count(&self) -> usize {
+        self.children.len()
+    }
+}
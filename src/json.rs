@@ -0,0 +1,477 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! JSON representation of issues
+//!
+//! This module provides the [Document] type, a self-contained representation
+//! of a whole issue which may be serialized as JSON. Documents allow moving
+//! issues between unrelated repositories and feeding them to other tools.
+//!
+//! A document holds all messages of an issue, including their parents,
+//! signatures and parsed trailers, as well as the targets of the issue's head
+//! and leaf references. Ids are represented as strings. The format is
+//! versioned via [Document::version]. Documents of a version other than
+//! [FORMAT_VERSION] are rejected.
+//!
+//! Dumps of other issue trackers may be imported via [dump::Importer].
+
+pub mod dump;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{self, ResultExt};
+use crate::issue::Issue;
+use crate::message::Message as _;
+use crate::object::Database;
+use crate::object::commit::Commit;
+use crate::reference::{self, Reference, Store};
+use crate::repository::Repository;
+use crate::trailer::TrailerValue;
+use crate::traversal::Traversible;
+
+/// Version of the format produced by this library
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Representation of a whole issue
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Document {
+    /// Version of the format
+    pub version: u32,
+    /// Id of the issue, i.e. of its initial message
+    pub issue: String,
+    /// Target of the issue's head reference
+    ///
+    /// This is the local head or, if there is none, the first remote head.
+    pub head: Option<String>,
+    /// Targets of the issue's leaf references
+    ///
+    /// These are the local leaves or, if there is no local head, the leaves of
+    /// all remotes.
+    pub leaves: Vec<String>,
+    /// The messages of the issue
+    ///
+    /// Messages are ordered like the nodes of the issue's
+    /// [Thread](crate::issue::thread::Thread).
+    pub messages: Vec<Message>,
+}
+
+impl Document {
+    /// Create a document from an issue
+    pub fn from_issue<'r, R>(issue: &Issue<'r, R>) -> error::Result<Self, R::InnerError>
+    where
+        R: Database<'r> + Traversible<'r> + Store<'r>,
+    {
+        let repo = issue.repo();
+        let messages = issue
+            .thread()?
+            .iter()
+            .map(|n| Message::from_commit::<R>(&repo.find_commit(n.id().clone())?))
+            .collect::<Result<_, _>>()?;
+
+        let local = issue.local_head()?.is_some();
+        let head = issue
+            .all_heads()?
+            .filter_map(|r| r.map(|r| r.target()).transpose())
+            .next()
+            .transpose()?
+            .map(|t| t.to_string());
+        let refs: Vec<_> = if local {
+            issue.local_refs()?.into_iter().collect()
+        } else {
+            issue.all_remote_refs()?.collect()
+        };
+        let mut leaves = Vec::new();
+        for reference in refs {
+            let reference = reference.wrap_with_kind(error::Kind::CannotGetReference)?;
+            let is_leaf = reference
+                .parts()
+                .is_some_and(|p| matches!(p.kind, reference::Kind::Leaf(_)));
+            if is_leaf {
+                leaves.extend(reference.target().map(|t| t.to_string()));
+            }
+        }
+        leaves.sort_unstable();
+        leaves.dedup();
+
+        Ok(Self {
+            version: FORMAT_VERSION,
+            issue: issue.id().to_string(),
+            head,
+            leaves,
+            messages,
+        })
+    }
+
+    /// Recreate the issue represented by this document in a repository
+    ///
+    /// All messages are recreated with the same parents, signatures and texts.
+    /// Parents which are not part of the document are retained only if they
+    /// are present in the target repository. The local head and leaf
+    /// references of the issue are set to the messages recreated. Documents
+    /// without a head are rejected, since the issue would not be discoverable.
+    pub fn import<'r, R>(&self, repo: &'r R) -> error::Result<Issue<'r, R>, R::InnerError>
+    where
+        R: Repository<'r> + Database<'r>,
+        R::Oid: Eq + Hash + FromStr,
+    {
+        if self.version != FORMAT_VERSION {
+            return Err(error::Kind::InvalidDocument(format!(
+                "unsupported version {}",
+                self.version
+            ))
+            .into());
+        }
+        if self.head.is_none() {
+            return Err(error::Kind::InvalidDocument("missing head".to_owned()).into());
+        }
+
+        let known: HashMap<_, _> = self.messages.iter().map(|m| (m.id.as_str(), m)).collect();
+        let mut ids: HashMap<&str, R::Oid> = HashMap::new();
+        let mut pending: Vec<_> = self.messages.iter().collect();
+        while !pending.is_empty() {
+            let before = pending.len();
+            let mut deferred = Vec::new();
+            for message in pending {
+                let ready = message
+                    .parents()
+                    .all(|p| ids.contains_key(p) || !known.contains_key(p));
+                if !ready {
+                    deferred.push(message);
+                    continue;
+                }
+
+                let mut parents = Vec::new();
+                for parent in message.parents() {
+                    let parent = match ids.get(parent) {
+                        Some(id) => id.clone(),
+                        None => match parent.parse() {
+                            Ok(id) => id,
+                            Err(_) => continue,
+                        },
+                    };
+                    if let Ok(parent) = repo.find_commit(parent) {
+                        parents.push(parent);
+                    }
+                }
+
+                let id = repo
+                    .commit_builder(|_, o| Ok(o))?
+                    .with_author(message.author.to_signature(repo)?)
+                    .with_committer(message.committer.to_signature(repo)?)
+                    .with_parents(parents)
+                    .build(&message.text)?;
+                ids.insert(&message.id, id);
+            }
+
+            if deferred.len() == before {
+                return Err(error::Kind::InvalidDocument("cyclic parents".to_owned()).into());
+            }
+            pending = deferred;
+        }
+
+        let lookup = |id: &str| {
+            ids.get(id)
+                .cloned()
+                .ok_or_else(|| error::Kind::InvalidDocument(format!("unknown message {id}")))
+        };
+        let issue = Issue::new_unchecked(repo, lookup(&self.issue)?);
+        if let Some(head) = &self.head {
+            issue.update_head(lookup(head)?, true)?;
+        }
+        for leaf in &self.leaves {
+            issue.add_leaf(lookup(leaf)?)?;
+        }
+        Ok(issue)
+    }
+
+    /// Read a document from its JSON representation
+    ///
+    /// Documents of an unsupported version are rejected.
+    pub fn read(reader: impl io::Read) -> io::Result<Self> {
+        let document: Self = serde_json::from_reader(reader)?;
+        if document.version != FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported issue document version {}", document.version),
+            ));
+        }
+        Ok(document)
+    }
+
+    /// Write the JSON representation of this document
+    pub fn write(&self, writer: impl io::Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(writer, self).map_err(Into::into)
+    }
+}
+
+/// Representation of a single message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+    /// Id of the message
+    pub id: String,
+    /// The first parent of the message, i.e. the message replied to
+    pub parent: Option<String>,
+    /// Any additional parents of the message
+    pub references: Vec<String>,
+    /// Author of the message
+    pub author: Signature,
+    /// Committer of the message
+    pub committer: Signature,
+    /// Subject of the message
+    pub subject: String,
+    /// Trailers of the message
+    pub trailers: Vec<Trailer>,
+    /// The full text of the message
+    pub text: String,
+}
+
+impl Message {
+    /// Create a representation of a commit
+    fn from_commit<'r, R: Database<'r>>(commit: &R::Commit) -> error::Result<Self, R::InnerError> {
+        let mut parents = commit.parent_ids().into_iter().map(|p| p.to_string());
+        let parent = parents.next();
+        let text = commit
+            .message()
+            .wrap_with_kind(error::Kind::MalformedMessage)?;
+        let subject = commit
+            .subject()
+            .wrap_with_kind(error::Kind::MalformedMessage)?;
+        let trailers = commit
+            .trailers()
+            .wrap_with_kind(error::Kind::MalformedMessage)?
+            .map(|t| Trailer {
+                key: t.key.to_string(),
+                value: t.value,
+            })
+            .collect();
+
+        Ok(Self {
+            id: commit.id().to_string(),
+            parent,
+            references: parents.collect(),
+            author: Signature::new::<R>(commit.author())?,
+            committer: Signature::new::<R>(commit.committer())?,
+            subject: subject.to_owned(),
+            trailers,
+            text: text.to_owned(),
+        })
+    }
+
+    /// Retrieve all parents of the message, starting with the first one
+    pub fn parents(&self) -> impl Iterator<Item = &str> + '_ {
+        self.parent
+            .iter()
+            .chain(self.references.iter())
+            .map(String::as_str)
+    }
+}
+
+/// Representation of a signature
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signature {
+    /// Name of the signatory
+    pub name: String,
+    /// Email address of the signatory
+    pub email: String,
+    /// Time of the signature in seconds since the Unix epoch
+    pub time: i64,
+    /// Timezone offset of the signature in minutes
+    pub offset: i32,
+}
+
+impl Signature {
+    /// Create a representation of a signature
+    fn new<'r, R: Database<'r>>(
+        signature: impl crate::object::signature::Signature,
+    ) -> error::Result<Self, R::InnerError> {
+        Ok(Self {
+            name: signature
+                .name()
+                .wrap_with_kind(error::Kind::CannotGetSignature)?
+                .to_owned(),
+            email: signature
+                .email()
+                .wrap_with_kind(error::Kind::CannotGetSignature)?
+                .to_owned(),
+            time: signature.time(),
+            offset: signature.offset(),
+        })
+    }
+
+    /// Create a signature for use with a repository
+    fn to_signature<'r, R: Database<'r>>(
+        &self,
+        repo: &'r R,
+    ) -> error::Result<R::Signature<'r>, R::InnerError> {
+        repo.signature(&self.name, &self.email, self.time, self.offset)
+    }
+}
+
+/// Representation of a trailer
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trailer {
+    /// Key of the trailer
+    pub key: String,
+    /// Parsed value of the trailer
+    pub value: TrailerValue,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory;
+
+    type TestRepo = memory::Repository;
+
+    /// Replace all ids in a document with the index of the message
+    fn normalize(mut document: Document) -> Document {
+        let index: HashMap<_, _> = document
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(n, m)| (m.id.clone(), n.to_string()))
+            .collect();
+        let map = |id: &mut String| *id = index.get(id).cloned().unwrap_or_default();
+
+        map(&mut document.issue);
+        document.head.iter_mut().for_each(map);
+        document.leaves.iter_mut().for_each(map);
+        document.leaves.sort_unstable();
+        for message in &mut document.messages {
+            map(&mut message.id);
+            message.parent.iter_mut().for_each(map);
+            message.references.iter_mut().for_each(map);
+        }
+        document
+    }
+
+    #[test]
+    fn roundtrip() {
+        let repo = TestRepo::default();
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .with_author(memory::Signature::new("Foo", "foo@example.com").with_time(10, 60))
+            .build("Test issue\n\nDit-type: bug\nDit-priority: 3\n")
+            .expect("Could not create issue");
+        let reply = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(repo.find_commit(*issue.id()).expect("No such message"))
+            .build("Reply")
+            .expect("Could not add message");
+        issue
+            .head_builder()
+            .expect("Could not create builder")
+            .with_parent(repo.find_commit(reply).expect("No such message"))
+            .build("Close\n\nDit-status: closed\n")
+            .expect("Could not add message");
+
+        let document = Document::from_issue(&issue).expect("Could not create document");
+        assert_eq!(document.version, FORMAT_VERSION);
+        assert_eq!(document.messages.len(), 3);
+        assert_eq!(document.messages[0].author.offset, 60);
+        assert_eq!(
+            document.messages[0].trailers,
+            [
+                Trailer {
+                    key: "Dit-type".to_owned(),
+                    value: TrailerValue::String("bug".to_owned()),
+                },
+                Trailer {
+                    key: "Dit-priority".to_owned(),
+                    value: TrailerValue::Int(3),
+                },
+            ],
+        );
+
+        let mut json = Vec::new();
+        document.write(&mut json).expect("Could not write document");
+        let read = Document::read(json.as_slice()).expect("Could not read document");
+        assert_eq!(read, document);
+
+        let other = TestRepo::default();
+        other
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .build("Unrelated")
+            .expect("Cannot create commit");
+        let imported = read.import(&other).expect("Could not import issue");
+        let recreated = Document::from_issue(&imported).expect("Could not create document");
+        assert_ne!(recreated.issue, document.issue);
+        assert_eq!(normalize(recreated), normalize(document));
+
+        let mut future = read;
+        future.version = FORMAT_VERSION + 1;
+        let mut json = Vec::new();
+        future.write(&mut json).expect("Could not write document");
+        assert!(Document::read(json.as_slice()).is_err());
+        assert!(future.import(&other).is_err());
+    }
+
+    #[test]
+    fn remote_only() {
+        use std::path::Path;
+
+        use crate::reference::Store as _;
+
+        let repo: TestRepo = (
+            memory::Store::default().with_remotes(["origin".to_owned()]),
+            Default::default(),
+        );
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test issue")
+            .expect("Could not create issue");
+        let reply = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(repo.find_commit(*issue.id()).expect("No such message"))
+            .build("Reply")
+            .expect("Could not add message");
+        for reference in issue.local_refs().expect("Could not get references") {
+            let reference = reference.expect("Could not get reference");
+            let path = reference.as_path().expect("Invalid reference name");
+            let remote = Path::new("refs/remotes/origin")
+                .join(path.strip_prefix("refs").expect("Not a ref"));
+            repo.0
+                .set_reference(&remote, reference.target().expect("No target"), false, "")
+                .expect("Could not set reference");
+            repo.0
+                .delete_reference(path)
+                .expect("Could not delete reference");
+        }
+
+        let document = Document::from_issue(&issue).expect("Could not create document");
+        assert_eq!(document.head, Some(issue.id().to_string()));
+        assert_eq!(document.leaves, [reply.to_string()]);
+
+        let other = TestRepo::default();
+        let imported = document.import(&other).expect("Could not import issue");
+        let issues: Vec<_> = other
+            .issues()
+            .expect("Could not list issues")
+            .into_iter()
+            .map(|i| *i.id())
+            .collect();
+        assert_eq!(issues, [*imported.id()]);
+        let recreated = Document::from_issue(&imported).expect("Could not create document");
+        assert_eq!(normalize(recreated), normalize(document.clone()));
+
+        let headless = Document {
+            head: None,
+            ..document
+        };
+        assert!(headless.import(&TestRepo::default()).is_err());
+    }
+}
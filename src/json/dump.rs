@@ -0,0 +1,580 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Import of issue tracker dumps
+//!
+//! This module provides the [Importer] for creating issues from offline dumps
+//! of the GitHub or GitLab issue APIs. A dump is a JSON array of [Record]s,
+//! each holding one issue as returned by the API with its comments (or
+//! "notes") and, optionally, its events embedded.
+//!
+//! The body of an issue becomes the initial message of a new issue. Comments
+//! become replies, each replying to the previous comment. The state, labels
+//! and assignees of the issue are recorded as `Dit-status`, `Dit-label` and
+//! `Dit-assignee` trailers in messages created via
+//! [Issue::head_builder](crate::issue::Issue::head_builder). Each event
+//! becomes a separate message in that head chain. The authors and dates of
+//! all messages are taken from the dump.
+
+use std::collections::BTreeSet;
+use std::io;
+
+use serde::Deserialize;
+
+use crate::error;
+use crate::issue::Issue;
+use crate::message::Composer;
+use crate::object::Database;
+use crate::repository::Repository;
+use crate::trailer::Trailer;
+use crate::trailer::spec::{ISSUE_ASSIGNEE_SPEC, ISSUE_LABEL_SPEC, ISSUE_STATUS_SPEC};
+use crate::utils;
+
+/// A single issue in a dump
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Record {
+    /// Number of the issue in the original tracker
+    #[serde(alias = "iid")]
+    pub number: u64,
+    /// Title of the issue
+    pub title: String,
+    /// Body of the issue
+    #[serde(default, alias = "description")]
+    pub body: Option<String>,
+    /// Author of the issue
+    #[serde(alias = "author")]
+    pub user: User,
+    /// Time at which the issue was created
+    pub created_at: String,
+    /// Current state of the issue
+    pub state: String,
+    /// Time at which the issue was closed
+    #[serde(default)]
+    pub closed_at: Option<String>,
+    /// User who closed the issue
+    #[serde(default)]
+    pub closed_by: Option<User>,
+    /// Current labels of the issue
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// Current assignees of the issue
+    #[serde(default)]
+    pub assignees: Vec<User>,
+    /// Comments on the issue
+    #[serde(default, alias = "notes")]
+    pub comments: Vec<Comment>,
+    /// Events of the issue
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+impl Record {
+    /// Check whether the issue is closed
+    pub fn is_closed(&self) -> bool {
+        self.state == "closed"
+    }
+}
+
+/// A user of the original tracker
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct User {
+    /// Login name of the user
+    #[serde(alias = "username")]
+    pub login: String,
+    /// Full name of the user, if available
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Email address of the user, if available
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// A label
+///
+/// GitHub provides labels as objects, GitLab as plain strings.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Label {
+    /// A label represented as an object
+    Object {
+        /// Name of the label
+        name: String,
+    },
+    /// A label represented by its name
+    Name(String),
+}
+
+impl Label {
+    /// Retrieve the name of the label
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Object { name } => name,
+            Self::Name(name) => name,
+        }
+    }
+}
+
+/// A comment on an issue
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Comment {
+    /// Body of the comment
+    pub body: String,
+    /// Author of the comment
+    #[serde(alias = "author")]
+    pub user: User,
+    /// Time at which the comment was created
+    pub created_at: String,
+    /// Whether the comment was generated by the tracker
+    ///
+    /// System notes are not imported.
+    #[serde(default)]
+    pub system: bool,
+}
+
+/// An event of an issue
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Event {
+    /// Kind of the event, e.g. `closed` or `labeled`
+    #[serde(alias = "state")]
+    pub event: String,
+    /// User who triggered the event
+    #[serde(default, alias = "user")]
+    pub actor: Option<User>,
+    /// Time of the event
+    pub created_at: String,
+    /// The label added or removed
+    #[serde(default)]
+    pub label: Option<Label>,
+    /// The user assigned or unassigned
+    #[serde(default)]
+    pub assignee: Option<User>,
+}
+
+/// Read a dump
+///
+/// The dump may either consist of an array of [Record]s or a single one.
+pub fn read(reader: impl io::Read) -> io::Result<Vec<Record>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Dump {
+        Many(Vec<Record>),
+        Single(Box<Record>),
+    }
+
+    match serde_json::from_reader(reader)? {
+        Dump::Many(records) => Ok(records),
+        Dump::Single(record) => Ok(vec![*record]),
+    }
+}
+
+/// Importer for dumps
+///
+/// Users are represented by their name, or their login if the name is not
+/// available. Users without an email address are assigned an address in a
+/// configurable domain, derived from their login.
+pub struct Importer<'r, R> {
+    repo: &'r R,
+    domain: String,
+}
+
+impl<'r, R> Importer<'r, R>
+where
+    R: Repository<'r> + Database<'r>,
+{
+    /// Create a new importer for a repository
+    ///
+    /// The `domain` is used for deriving email addresses of users.
+    pub fn new(repo: &'r R, domain: impl Into<String>) -> Self {
+        Self {
+            repo,
+            domain: domain.into(),
+        }
+    }
+
+    /// Import all records of a dump
+    pub fn import_all<'a>(
+        &self,
+        records: impl IntoIterator<Item = &'a Record>,
+    ) -> error::Result<Vec<Issue<'r, R>>, R::InnerError> {
+        records.into_iter().map(|r| self.import(r)).collect()
+    }
+
+    /// Import a single record as a new issue
+    pub fn import(&self, record: &Record) -> error::Result<Issue<'r, R>, R::InnerError> {
+        let issue = self
+            .repo
+            .issue_builder()?
+            .with_author(self.signature(&record.user, &record.created_at)?)
+            .with_committer(self.signature(&record.user, &record.created_at)?)
+            .build(Composer::new(&record.title).with_body(normalize(record.body.as_deref())))?;
+
+        let mut comments: Vec<_> = record
+            .comments
+            .iter()
+            .filter(|c| !c.system)
+            .map(|c| Ok((parse_date::<R>(&c.created_at)?.0, c)))
+            .collect::<error::Result<_, _>>()?;
+        comments.sort_by_key(|(time, _)| *time);
+
+        let subject = format!("Re: {}", record.title);
+        let mut discussion = Vec::new();
+        let mut parent = self.repo.find_commit(issue.id().clone())?;
+        for (time, comment) in comments {
+            let message = issue
+                .message_builder()?
+                .with_author(self.signature(&comment.user, &comment.created_at)?)
+                .with_committer(self.signature(&comment.user, &comment.created_at)?)
+                .with_parent(parent)
+                .build(Composer::new(&subject).with_body(normalize(Some(&comment.body))))?;
+            parent = self.repo.find_commit(message.clone())?;
+            discussion.push((time, message));
+        }
+
+        let mut chain = HeadChain {
+            importer: self,
+            issue: &issue,
+            discussion,
+            state: Default::default(),
+        };
+        let opened = if record.events.is_empty() {
+            State {
+                closed: false,
+                labels: record.labels.iter().map(|l| l.name().to_owned()).collect(),
+                assignees: record.assignees.iter().map(|a| a.login.clone()).collect(),
+            }
+        } else {
+            Default::default()
+        };
+        chain.transition(&record.user, &record.created_at, "Open issue", opened)?;
+
+        let mut events: Vec<_> = record
+            .events
+            .iter()
+            .map(|e| Ok((parse_date::<R>(&e.created_at)?.0, e)))
+            .collect::<error::Result<_, _>>()?;
+        events.sort_by_key(|(time, _)| *time);
+        for (_, event) in events {
+            let mut state = chain.state.clone();
+            let subject = match (event.event.as_str(), &event.label, &event.assignee) {
+                ("closed", _, _) => {
+                    state.closed = true;
+                    "Close issue"
+                }
+                ("reopened", _, _) => {
+                    state.closed = false;
+                    "Reopen issue"
+                }
+                ("labeled", Some(label), _) => {
+                    state.labels.insert(label.name().to_owned());
+                    "Add label"
+                }
+                ("unlabeled", Some(label), _) => {
+                    state.labels.remove(label.name());
+                    "Remove label"
+                }
+                ("assigned", _, Some(user)) => {
+                    state.assignees.insert(user.login.clone());
+                    "Assign issue"
+                }
+                ("unassigned", _, Some(user)) => {
+                    state.assignees.remove(&user.login);
+                    "Unassign issue"
+                }
+                _ => continue,
+            };
+            let actor = event.actor.as_ref().unwrap_or(&record.user);
+            chain.transition(actor, &event.created_at, subject, state)?;
+        }
+
+        let mut state = State {
+            closed: chain.state.closed,
+            labels: record.labels.iter().map(|l| l.name().to_owned()).collect(),
+            assignees: record.assignees.iter().map(|a| a.login.clone()).collect(),
+        };
+        let last = chain.last_date(record);
+        chain.transition(&record.user, last, "Update metadata", state.clone())?;
+        if record.is_closed() != state.closed {
+            state.closed = record.is_closed();
+            let (actor, date, subject) = if state.closed {
+                let actor = record.closed_by.as_ref().unwrap_or(&record.user);
+                (
+                    actor,
+                    record.closed_at.as_deref().unwrap_or(last),
+                    "Close issue",
+                )
+            } else {
+                (&record.user, last, "Reopen issue")
+            };
+            chain.transition(actor, date, subject, state)?;
+        }
+
+        Ok(issue)
+    }
+
+    /// Create a signature for a user at a given time
+    fn signature(&self, user: &User, date: &str) -> error::Result<R::Signature<'r>, R::InnerError> {
+        let (time, offset) = parse_date::<R>(date)?;
+        let name = user.name.as_deref().unwrap_or(&user.login);
+        let email = user
+            .email
+            .clone()
+            .unwrap_or_else(|| format!("{}@{}", user.login, self.domain));
+        self.repo.signature(name, &email, time, offset)
+    }
+}
+
+/// Metadata of an issue
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct State {
+    closed: bool,
+    labels: BTreeSet<String>,
+    assignees: BTreeSet<String>,
+}
+
+impl State {
+    /// Compute the trailers transforming this state into another one
+    fn trailers(&self, new: &Self) -> Vec<Trailer> {
+        let changes = |key: &str, old: &BTreeSet<String>, new: &BTreeSet<String>| {
+            let key = key.to_owned();
+            let added = new.difference(old).map(|v| format!("+{v}"));
+            let removed = old.difference(new).map(|v| format!("-{v}"));
+            added
+                .chain(removed)
+                .map(|v| Trailer::new(&key, &v))
+                .collect::<Vec<_>>()
+        };
+
+        let mut trailers = Vec::new();
+        if self.closed != new.closed || self == &Self::default() {
+            let status = if new.closed { "closed" } else { "open" };
+            trailers.push(Trailer::new(&ISSUE_STATUS_SPEC.key, status));
+        }
+        trailers.extend(changes(&ISSUE_LABEL_SPEC.key, &self.labels, &new.labels));
+        trailers.extend(changes(
+            &ISSUE_ASSIGNEE_SPEC.key,
+            &self.assignees,
+            &new.assignees,
+        ));
+        trailers
+    }
+}
+
+/// Helper for building the head chain of an issue
+struct HeadChain<'i, 'r, R: Database<'r>> {
+    importer: &'i Importer<'r, R>,
+    issue: &'i Issue<'r, R>,
+    discussion: Vec<(i64, R::Oid)>,
+    state: State,
+}
+
+impl<'r, R> HeadChain<'_, 'r, R>
+where
+    R: Repository<'r> + Database<'r>,
+{
+    /// Record a transition to a new state
+    ///
+    /// If the new state differs from the current one, a message is added to
+    /// the head chain. The latest comment created before the transition is
+    /// referenced by the message.
+    fn transition(
+        &mut self,
+        actor: &User,
+        date: &str,
+        subject: &str,
+        state: State,
+    ) -> error::Result<(), R::InnerError> {
+        let trailers = self.state.trailers(&state);
+        if trailers.is_empty() {
+            return Ok(());
+        }
+
+        let (time, _) = parse_date::<R>(date)?;
+        let mut builder = self
+            .issue
+            .head_builder()?
+            .with_author(self.importer.signature(actor, date)?)
+            .with_committer(self.importer.signature(actor, date)?);
+        let comment = self
+            .discussion
+            .iter()
+            .rev()
+            .find(|(t, _)| *t <= time)
+            .map(|(_, m)| m.clone());
+        if let Some(comment) = comment {
+            builder = builder.with_parent(self.importer.repo.find_commit(comment)?);
+        }
+        builder.build(Composer::new(subject).with_trailers(trailers))?;
+        self.state = state;
+        Ok(())
+    }
+
+    /// Retrieve the date of the latest known activity of a record
+    fn last_date<'a>(&self, record: &'a Record) -> &'a str {
+        let dates = std::iter::once(&record.created_at)
+            .chain(record.comments.iter().map(|c| &c.created_at))
+            .chain(record.events.iter().map(|e| &e.created_at));
+        dates
+            .filter_map(|d| utils::parse_rfc3339(d).map(|(t, _)| (t, d)))
+            .max_by_key(|(t, _)| *t)
+            .map(|(_, d)| d.as_str())
+            .unwrap_or(&record.created_at)
+    }
+}
+
+/// Parse a date as provided by the issue tracker APIs
+fn parse_date<'r, R: Database<'r>>(date: &str) -> error::Result<(i64, i32), R::InnerError> {
+    utils::parse_rfc3339(date)
+        .ok_or_else(|| error::Kind::InvalidDocument(format!("malformed date {date}")).into())
+}
+
+/// Normalize a text provided by the issue tracker APIs
+fn normalize(text: Option<&str>) -> String {
+    text.unwrap_or_default().replace("\r\n", "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory;
+    use crate::message::Message;
+    use crate::object::commit::Commit;
+    use crate::object::signature::Signature;
+    use crate::trailer::TrailerValue;
+    use crate::trailer::accumulation::ValueAccumulator;
+
+    type TestRepo = memory::Repository;
+
+    #[test]
+    fn import() {
+        let dump = r#"[
+            {
+                "number": 1,
+                "title": "Crash on start",
+                "body": "It crashes.\r\n\r\nEvery time.",
+                "user": {"login": "alice"},
+                "created_at": "2023-11-14T20:00:00Z",
+                "state": "closed",
+                "closed_at": "2023-11-16T08:00:00+01:00",
+                "closed_by": {"login": "bob", "name": "Bob B", "email": "bob@example.com"},
+                "labels": [{"name": "bug"}, {"name": "crash"}],
+                "assignees": [{"login": "bob"}],
+                "comments": [
+                    {"body": "Fixed.", "user": {"login": "bob"}, "created_at": "2023-11-16T06:00:00Z"},
+                    {"body": "Cannot reproduce.", "user": {"login": "bob"}, "created_at": "2023-11-15T10:00:00Z"}
+                ],
+                "events": [
+                    {"event": "labeled", "actor": {"login": "bob"}, "created_at": "2023-11-15T09:00:00Z", "label": {"name": "bug"}},
+                    {"event": "labeled", "actor": {"login": "bob"}, "created_at": "2023-11-15T09:30:00Z", "label": {"name": "wontfix"}}
+                ]
+            },
+            {
+                "iid": 2,
+                "title": "Feature",
+                "description": null,
+                "author": {"username": "carol"},
+                "created_at": "2023-11-17T00:00:00.000Z",
+                "state": "opened",
+                "labels": ["feature"],
+                "notes": [
+                    {"body": "changed the description", "author": {"username": "carol"}, "created_at": "2023-11-17T01:00:00Z", "system": true}
+                ]
+            }
+        ]"#;
+        let records = read(dump.as_bytes()).expect("Could not read dump");
+        assert_eq!(records.len(), 2);
+
+        let repo = TestRepo::default();
+        let issues = Importer::new(&repo, "users.example.com")
+            .import_all(&records)
+            .expect("Could not import dump");
+        let specs = [ISSUE_STATUS_SPEC, ISSUE_LABEL_SPEC, ISSUE_ASSIGNEE_SPEC];
+        let values = |issue: &Issue<'_, TestRepo>, key: &str| -> Vec<TrailerValue> {
+            let metadata: std::collections::HashMap<String, ValueAccumulator> =
+                issue.metadata(&specs[..]).expect("Could not get metadata");
+            metadata.get(key).cloned().into_iter().flatten().collect()
+        };
+
+        let first = &issues[0];
+        let initial = repo.find_commit(*first.id()).expect("No such message");
+        assert_eq!(
+            initial.message(),
+            Ok("Crash on start\n\nIt crashes.\n\nEvery time.\n"),
+        );
+        assert_eq!(initial.author().email(), Ok("alice@users.example.com"));
+        assert_eq!(initial.author().time(), 1699992000);
+        assert_eq!(
+            values(first, "Dit-status"),
+            [TrailerValue::String("closed".to_owned())],
+        );
+        assert_eq!(
+            values(first, "Dit-label"),
+            [
+                TrailerValue::String("bug".to_owned()),
+                TrailerValue::String("crash".to_owned()),
+            ],
+        );
+        assert_eq!(
+            values(first, "Dit-assignee"),
+            [TrailerValue::String("bob".to_owned())],
+        );
+
+        let head = first
+            .local_head()
+            .expect("Could not get head")
+            .and_then(|r| crate::reference::Reference::target(&r))
+            .expect("No head");
+        let close = repo.find_commit(head).expect("No such message");
+        assert_eq!(close.subject(), Ok("Close issue"));
+        assert_eq!(close.author().name(), Ok("Bob B"));
+        assert_eq!(close.author().offset(), 60);
+        let fixed = repo
+            .find_commit(close.parent_ids().into_iter().nth(1).expect("No reference"))
+            .expect("No such message");
+        assert_eq!(fixed.message(), Ok("Re: Crash on start\n\nFixed.\n"));
+        let reply = repo
+            .find_commit(fixed.parent_ids().into_iter().next().expect("No parent"))
+            .expect("No such message");
+        assert_eq!(
+            reply.message(),
+            Ok("Re: Crash on start\n\nCannot reproduce.\n"),
+        );
+        assert_eq!(first.messages().expect("No messages").count(), 8);
+
+        let second = &issues[1];
+        assert_eq!(
+            values(second, "Dit-status"),
+            [TrailerValue::String("open".to_owned())],
+        );
+        assert_eq!(
+            values(second, "Dit-label"),
+            [TrailerValue::String("feature".to_owned())],
+        );
+        assert_eq!(second.messages().expect("No messages").count(), 2);
+    }
+
+    #[test]
+    fn malformed_date() {
+        let dump = r#"[
+            {
+                "number": 1,
+                "title": "Crash on start",
+                "user": {"login": "alice"},
+                "created_at": "99999999999999999-01-01T00:00:00Z",
+                "state": "open"
+            }
+        ]"#;
+        let records = read(dump.as_bytes()).expect("Could not read dump");
+        let repo = TestRepo::default();
+        let error = Importer::new(&repo, "users.example.com")
+            .import(&records[0])
+            .err()
+            .expect("Imported malformed date");
+        assert!(
+            matches!(error.kind(), error::Kind::InvalidDocument(m) if m.starts_with("malformed date"))
+        );
+    }
+}
@@ -8,9 +8,10 @@
 //! The git-dit library
 //!
 //! This library provides low-level functionality for accessing, creating and
-//! manipulating "git-dit" issues and messages. It is implemented on top of the
-//! `git2` crate. This librarie's documentation primarily provides information
-//! about its API and abstract processing of issues and messages.
+//! manipulating "git-dit" issues and messages. It is implemented on top of
+//! either the `git2` or the `gix` crate, selected via the features of the same
+//! name. This librarie's documentation primarily provides information about its
+//! API and abstract processing of issues and messages.
 //!
 //!
 //! # Issues
@@ -32,15 +33,28 @@
 //!
 
 pub mod base;
+pub mod cache;
+pub mod crossref;
 pub mod error;
+pub mod event;
+pub mod feed;
 pub mod gc;
+pub mod index;
 pub mod issue;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod mbox;
+#[cfg(any(test, feature = "memory"))]
+pub mod memory;
+pub mod message;
 pub mod object;
 pub mod reference;
 pub mod remote;
 pub mod repository;
+pub mod site;
 pub mod trailer;
 pub mod traversal;
+pub(crate) mod utils;
 
 // A selection of types are reexported for more convenient access.
 pub use error::Error;
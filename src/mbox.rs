@@ -0,0 +1,272 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Mbox export and import
+//!
+//! This module provides facilities for exporting issues as mailboxes in the
+//! `mboxrd` format. Each message is rendered as an email as specified in
+//! RFC 5322. The `Message-ID` of each mail is derived from the id of the
+//! message it represents. The `In-Reply-To` and `References` headers reflect
+//! the reply structure of the issue, i.e. the first parents of messages.
+//!
+//! Mails may be imported as messages via the [import::Importer].
+
+pub mod import;
+pub mod mail;
+
+use std::fmt::{self, Write};
+
+use crate::error::{self, ResultExt};
+use crate::issue::Issue;
+use crate::object::Database;
+use crate::object::commit::Commit;
+use crate::object::signature::Signature;
+use crate::reference::Store;
+use crate::traversal::Traversible;
+use crate::utils::DateTime;
+
+/// Domain used for the `Message-ID`s of exported messages
+pub const MESSAGE_ID_DOMAIN: &str = "git-dit";
+
+/// Date used in the separator line preceding each mail
+///
+/// Like `git format-patch`, we use a fixed date in the separator lines.
+const SEPARATOR_DATE: &str = "Mon Sep 17 00:00:00 2001";
+
+/// Maximum length of the text encoded in a single RFC 2047 encoded word
+const ENCODED_WORD_TEXT_LEN: usize = 63;
+
+/// Retrieve the `Message-ID` for a message, including angle brackets
+pub fn message_id(id: &impl fmt::Display) -> String {
+    format!("<{id}@{MESSAGE_ID_DOMAIN}>")
+}
+
+/// Export all messages of an issue as an mbox
+///
+/// Messages are written in the pre-order of the issue's
+/// [Thread](crate::issue::thread::Thread), i.e. each message is followed by
+/// its replies.
+pub fn export<'r, R>(issue: &Issue<'r, R>, out: &mut impl Write) -> error::Result<(), R::InnerError>
+where
+    R: Database<'r> + Traversible<'r> + Store<'r>,
+{
+    let thread = issue.thread()?;
+    for node in &thread {
+        let mut references = Vec::new();
+        let mut parent = thread.parent(node);
+        while let Some(p) = parent {
+            references.push(p.id());
+            parent = thread.parent(p);
+        }
+        references.reverse();
+
+        let message = issue.repo().find_commit(node.id().clone())?;
+        write_message::<R>(&message, &references, out)?;
+    }
+    Ok(())
+}
+
+/// Write a single message as a mail, including the separator line
+///
+/// `references` contains the ancestors of the message within the reply tree,
+/// starting with the root and ending with the message replied to.
+fn write_message<'r, R: Database<'r>>(
+    message: &R::Commit,
+    references: &[&R::Oid],
+    out: &mut impl Write,
+) -> error::Result<(), R::InnerError> {
+    let text = message
+        .message()
+        .wrap_with_kind(error::Kind::MalformedMessage)?
+        .trim_start_matches('\n');
+    let (subject, body) = text.split_once("\n\n").unwrap_or((text, ""));
+    let subject = subject.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let author = message.author();
+    let name = author
+        .name()
+        .wrap_with_kind(error::Kind::CannotGetSignature)?;
+    let email = author
+        .email()
+        .wrap_with_kind(error::Kind::CannotGetSignature)?;
+    let date = DateTime::new(author.time(), author.offset());
+
+    write_headers(out, message.id(), references, name, email, &date, &subject)
+        .wrap_with_kind(error::Kind::CannotWriteMbox)?;
+    write_body(out, body).wrap_with_kind(error::Kind::CannotWriteMbox)
+}
+
+/// Write the separator line and headers of a mail
+fn write_headers<O: fmt::Display>(
+    out: &mut impl Write,
+    id: O,
+    references: &[&O],
+    name: &str,
+    email: &str,
+    date: &DateTime,
+    subject: &str,
+) -> fmt::Result {
+    writeln!(out, "From {id} {SEPARATOR_DATE}")?;
+    write!(out, "From: ")?;
+    write_display_name(out, name)?;
+    writeln!(out, " <{email}>")?;
+    writeln!(out, "Date: {}", date.rfc5322())?;
+    write!(out, "Subject: ")?;
+    write_text(out, subject)?;
+    writeln!(out)?;
+    writeln!(out, "Message-ID: {}", message_id(&id))?;
+    if let Some(parent) = references.last() {
+        writeln!(out, "In-Reply-To: {}", message_id(parent))?;
+        write!(out, "References:")?;
+        references
+            .iter()
+            .try_for_each(|r| write!(out, "\n {}", message_id(r)))?;
+        writeln!(out)?;
+    }
+    writeln!(out, "MIME-Version: 1.0")?;
+    writeln!(out, "Content-Type: text/plain; charset=utf-8")?;
+    writeln!(out, "Content-Transfer-Encoding: 8bit")?;
+    writeln!(out)
+}
+
+/// Write the body of a mail, followed by an empty line
+///
+/// Lines which could be mistaken for separator lines are quoted as specified
+/// for the `mboxrd` format.
+fn write_body(out: &mut impl Write, body: &str) -> fmt::Result {
+    for line in body.lines() {
+        if line.trim_start_matches('>').starts_with("From ") {
+            out.write_char('>')?;
+        }
+        writeln!(out, "{line}")?;
+    }
+    writeln!(out)
+}
+
+/// Write a display name for use in an address
+///
+/// The name will be quoted or encoded if necessary.
+fn write_display_name(out: &mut impl Write, name: &str) -> fmt::Result {
+    const SPECIALS: &[char] = &[
+        '(', ')', '<', '>', '[', ']', ':', ';', '@', '\\', ',', '.', '"',
+    ];
+
+    if !name.is_ascii() {
+        write_encoded_words(out, name)
+    } else if name.contains(SPECIALS) {
+        out.write_char('"')?;
+        for c in name.chars() {
+            if c == '"' || c == '\\' {
+                out.write_char('\\')?;
+            }
+            out.write_char(c)?;
+        }
+        out.write_char('"')
+    } else {
+        out.write_str(name)
+    }
+}
+
+/// Write unstructured text for use in a header
+///
+/// The text will be encoded if it contains any non-ASCII characters.
+fn write_text(out: &mut impl Write, text: &str) -> fmt::Result {
+    if text.is_ascii() {
+        out.write_str(text)
+    } else {
+        write_encoded_words(out, text)
+    }
+}
+
+/// Write text as a series of RFC 2047 encoded words using "Q" encoding
+fn write_encoded_words(out: &mut impl Write, text: &str) -> fmt::Result {
+    let mut word = String::new();
+    for c in text.chars() {
+        let mut encoded = String::new();
+        match c {
+            ' ' => encoded.push('_'),
+            c if c.is_ascii_alphanumeric() => encoded.push(c),
+            c => c
+                .encode_utf8(&mut [0; 4])
+                .bytes()
+                .try_for_each(|b| write!(encoded, "={b:02X}"))?,
+        }
+        if word.len() + encoded.len() > ENCODED_WORD_TEXT_LEN {
+            write!(out, "=?utf-8?q?{word}?=\n ")?;
+            word.clear();
+        }
+        word.push_str(&encoded);
+    }
+    write!(out, "=?utf-8?q?{word}?=")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory;
+    use crate::repository::Repository;
+
+    type TestRepo = memory::Repository;
+
+    #[test]
+    fn export_issue() {
+        let repo = TestRepo::default();
+        let author =
+            memory::Signature::new("Jöhn Doe", "john@example.com").with_time(1700000000, 60);
+
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .with_author(author.clone())
+            .build("Test issue\n\nFrom the beginning.\n>From here.\n\nStatus: open\n")
+            .expect("Could not create issue");
+        let reply = |parent, message| {
+            issue
+                .message_builder()
+                .expect("Could not create builder")
+                .with_author(memory::Signature::new("Doe, Jane", "jane@example.com"))
+                .with_parent(repo.find_commit(parent).expect("No such message"))
+                .build(message)
+                .expect("Could not add message")
+        };
+        let first = reply(*issue.id(), "Re: Test issue\n\nA reply\n");
+        let second = reply(first, "Re: Test issue\n\nStatus: closed\n");
+
+        let mut mbox = String::new();
+        export(&issue, &mut mbox).expect("Could not export issue");
+
+        let mails: Vec<_> = mbox.split("\nFrom ").collect();
+        assert_eq!(mails.len(), 3);
+        assert_eq!(
+            mails[0],
+            format!(
+                "From {0} Mon Sep 17 00:00:00 2001\n\
+                From: =?utf-8?q?J=C3=B6hn_Doe?= <john@example.com>\n\
+                Date: Tue, 14 Nov 2023 23:13:20 +0100\n\
+                Subject: Test issue\n\
+                Message-ID: <{0}@git-dit>\n\
+                MIME-Version: 1.0\n\
+                Content-Type: text/plain; charset=utf-8\n\
+                Content-Transfer-Encoding: 8bit\n\
+                \n\
+                >From the beginning.\n\
+                >>From here.\n\
+                \n\
+                Status: open\n",
+                issue.id(),
+            ),
+        );
+        assert!(mails[1].contains("From: \"Doe, Jane\" <jane@example.com>\n"));
+        assert!(mails[1].contains(&format!("In-Reply-To: <{}@git-dit>\n", issue.id())));
+        assert!(mails[2].starts_with(&format!("{second} ")));
+        assert!(mails[2].contains(&format!(
+            "In-Reply-To: <{first}@git-dit>\nReferences:\n <{}@git-dit>\n <{first}@git-dit>\n",
+            issue.id(),
+        )));
+        assert!(mails[2].ends_with("\n\nStatus: closed\n\n"));
+    }
+}
@@ -0,0 +1,363 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Import of mails
+//!
+//! This module provides the [Importer] for creating messages from [Mail]s. The
+//! `Message-ID`s of imported mails are recorded in a [Mapping], which may be
+//! persisted in order to make repeated imports of the same mails idempotent.
+//!
+//! A mapping is persisted in a simple line based text format. The first line
+//! identifies the format and its version. Each following line consists of a
+//! `Message-ID` and the id of the message created from the mail, separated by
+//! a tab.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::MESSAGE_ID_DOMAIN;
+use super::mail::Mail;
+use crate::base::Base;
+use crate::error;
+use crate::index::MessageIndex;
+use crate::object::Database;
+use crate::repository::Repository;
+use crate::traversal::Traversible;
+use crate::utils::LineFormat;
+
+/// Path of the mapping file relative to a repository's git directory
+pub const MAPPING_PATH: &str = "dit/mbox-mapping";
+
+/// Format of a persisted mapping
+const FORMAT: LineFormat = LineFormat {
+    header: "dit-mbox-mapping 1",
+    name: "mbox mapping",
+};
+
+/// Mapping from `Message-ID`s to the messages created from mails
+#[derive(Clone, Debug)]
+pub struct Mapping<O> {
+    messages: HashMap<String, O>,
+}
+
+impl<O> Mapping<O> {
+    /// Create a new, empty mapping
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Retrieve the message created for a `Message-ID`
+    pub fn get(&self, message_id: &str) -> Option<&O> {
+        self.messages.get(message_id)
+    }
+
+    /// Record the message created for a `Message-ID`
+    pub fn insert(&mut self, message_id: String, message: O) {
+        self.messages.insert(message_id, message);
+    }
+
+    /// Retrieve the number of mails recorded
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Check whether no mails are recorded
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+impl<O: fmt::Display + FromStr> Mapping<O> {
+    /// Load a mapping from a file
+    ///
+    /// If the file does not exist, an empty mapping is returned.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        FORMAT.load(path, Self::read)
+    }
+
+    /// Save the mapping to a file, replacing any previous version atomically
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        FORMAT.save(path, |file| self.write(file))
+    }
+
+    /// Read a mapping in the persisted format
+    pub fn read(reader: impl BufRead) -> io::Result<Self> {
+        let invalid = |line| FORMAT.invalid(line);
+        let Some(lines) = FORMAT.lines(reader)? else {
+            return Ok(Self::new());
+        };
+
+        let mut messages = HashMap::new();
+        for (n, line) in lines {
+            let line = line?;
+            let (id, message) = line.split_once('\t').ok_or_else(|| invalid(n))?;
+            let message = message.parse().map_err(|_| invalid(n))?;
+            messages.insert(id.to_owned(), message);
+        }
+        Ok(Self { messages })
+    }
+
+    /// Write the mapping in the persisted format
+    ///
+    /// Entries are written in the order of their `Message-ID`s.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        FORMAT.write_header(&mut writer)?;
+        let mut entries: Vec<_> = self.messages.iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        for (id, message) in entries {
+            writeln!(writer, "{id}\t{message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<O> Default for Mapping<O> {
+    fn default() -> Self {
+        Self {
+            messages: Default::default(),
+        }
+    }
+}
+
+/// Importer for mails
+///
+/// Each mail which does not reply to a known mail or message is imported as
+/// the initial message of a new issue. Replies are added to the issue of the
+/// message replied to, with that message as their first parent. The author of
+/// each message created is taken from the `From` and `Date` of the mail.
+///
+/// Mails already recorded in the [Mapping] are not imported again. Mails with
+/// a `Message-ID` derived from a message, e.g. by [super::export], are
+/// considered to be that message if it is present in the repository.
+pub struct Importer<'r, R: Base> {
+    repo: &'r R,
+    mapping: Mapping<R::Oid>,
+    index: MessageIndex<R::Oid>,
+}
+
+impl<'r, R> Importer<'r, R>
+where
+    R: Repository<'r> + Database<'r> + Traversible<'r>,
+    R::Oid: Eq + Hash + FromStr,
+{
+    /// Create a new importer for a repository using an existing [Mapping]
+    pub fn new(repo: &'r R, mapping: Mapping<R::Oid>) -> Self {
+        Self {
+            repo,
+            mapping,
+            index: Default::default(),
+        }
+    }
+
+    /// Retrieve the [Mapping] of this importer
+    pub fn mapping(&self) -> &Mapping<R::Oid> {
+        &self.mapping
+    }
+
+    /// Extract the [Mapping] of this importer
+    pub fn into_mapping(self) -> Mapping<R::Oid> {
+        self.mapping
+    }
+
+    /// Import mails
+    ///
+    /// Mails may be passed in any order. Replies are imported after the mails
+    /// they reply to. Returns the ids of the messages created.
+    pub fn import(
+        &mut self,
+        mails: impl IntoIterator<Item = Mail>,
+    ) -> error::Result<Vec<R::Oid>, R::InnerError> {
+        let mut pending: Vec<_> = mails.into_iter().collect();
+        let mut ids: HashSet<_> = HashSet::new();
+        pending
+            .retain(|m| ids.insert(m.message_id.clone()) && self.resolve(&m.message_id).is_none());
+
+        let mut res = Vec::new();
+        while !pending.is_empty() {
+            let before = pending.len();
+            let mut deferred = Vec::new();
+            for mail in pending {
+                match self.parent(&mail, &ids) {
+                    Some(parent) => res.push(self.add(&mail, parent)?),
+                    None => deferred.push(mail),
+                }
+            }
+
+            // Replies to mails which are themselves deferred indicate a cycle.
+            // We break it by importing one of the mails as a new issue.
+            if deferred.len() == before {
+                res.push(self.add(&deferred.remove(0), None)?);
+            }
+            pending = deferred;
+        }
+        Ok(res)
+    }
+
+    /// Determine the parent for a mail
+    ///
+    /// Returns `None` if the mail replies to a mail not yet imported, and
+    /// `Some(None)` if it does not reply to any known mail or message.
+    fn parent(&mut self, mail: &Mail, pending: &HashSet<String>) -> Option<Option<R::Oid>> {
+        for id in mail.parent_ids() {
+            if let Some(message) = self.resolve(id) {
+                return Some(Some(message));
+            }
+            if pending.contains(id) {
+                return None;
+            }
+        }
+        Some(None)
+    }
+
+    /// Resolve a `Message-ID` to the id of an existing message
+    fn resolve(&mut self, message_id: &str) -> Option<R::Oid> {
+        if let Some(message) = self.mapping.get(message_id) {
+            return Some(message.clone());
+        }
+
+        let message: R::Oid = message_id
+            .strip_prefix('<')?
+            .strip_suffix('>')?
+            .strip_suffix(MESSAGE_ID_DOMAIN)?
+            .strip_suffix('@')?
+            .parse()
+            .ok()?;
+        self.repo.find_commit(message.clone()).ok()?;
+        self.repo
+            .issue_with_message_indexed(&mut self.index, message.clone())
+            .ok()
+            .map(|_| message)
+    }
+
+    /// Create a message from a mail
+    ///
+    /// If no parent is given, the message is created as a new issue.
+    fn add(&mut self, mail: &Mail, parent: Option<R::Oid>) -> error::Result<R::Oid, R::InnerError> {
+        let author = self
+            .repo
+            .signature(&mail.name, &mail.email, mail.time, mail.offset)?;
+        let message = match parent {
+            Some(parent) => {
+                let issue = self
+                    .repo
+                    .issue_with_message_indexed(&mut self.index, parent.clone())?;
+                issue
+                    .message_builder()?
+                    .with_author(author)
+                    .with_parent(self.repo.find_commit(parent)?)
+                    .build(mail.message())?
+            }
+            None => self
+                .repo
+                .issue_builder()?
+                .with_author(author)
+                .build(mail.message())?
+                .id()
+                .clone(),
+        };
+        self.mapping
+            .insert(mail.message_id.clone(), message.clone());
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mbox::{export, mail};
+    use crate::memory;
+    use crate::object::commit::Commit;
+    use crate::object::signature::Signature;
+
+    type TestRepo = memory::Repository;
+
+    #[test]
+    fn import() {
+        let mbox = "From 1 Mon Sep 17 00:00:00 2001\n\
+            From: John Doe <john@example.com>\n\
+            Date: Tue, 14 Nov 2023 23:13:20 +0100\n\
+            Subject: Re: Test issue\n\
+            Message-ID: <2@example.com>\n\
+            In-Reply-To: <1@example.com>\n\
+            \n\
+            A reply\n\
+            \n\
+            From 1 Mon Sep 17 00:00:00 2001\n\
+            From: Jane Doe <jane@example.com>\n\
+            Date: Tue, 14 Nov 2023 20:13:20 -0200\n\
+            Subject: Test issue\n\
+            Message-ID: <1@example.com>\n\
+            \n\
+            Status: open\n\
+            \n";
+        let mails = mail::parse_mbox(mbox).expect("Could not parse mbox");
+
+        let repo = TestRepo::default();
+        let mut importer = Importer::new(&repo, Mapping::new());
+        let created = importer
+            .import(mails.clone())
+            .expect("Could not import mails");
+        assert_eq!(created.len(), 2);
+
+        let mapping = importer.into_mapping();
+        let initial = *mapping.get("<1@example.com>").expect("Mail not imported");
+        let reply = *mapping.get("<2@example.com>").expect("Mail not imported");
+        let issues: Vec<_> = repo
+            .issues()
+            .expect("Could not get issues")
+            .iter()
+            .map(|i| *i.id())
+            .collect();
+        assert_eq!(issues, [initial]);
+
+        let initial = repo.find_commit(initial).expect("No such message");
+        assert_eq!(initial.message(), Ok("Test issue\n\nStatus: open\n"));
+        assert_eq!(initial.author().name(), Ok("Jane Doe"));
+        assert_eq!(initial.author().time(), 1700000000);
+        assert_eq!(initial.author().offset(), -120);
+        let reply = repo.find_commit(reply).expect("No such message");
+        assert_eq!(reply.parent_ids().into_iter().next(), Some(initial.id()));
+        assert_eq!(reply.author().email(), Ok("john@example.com"));
+
+        let mut buf = Vec::new();
+        mapping.write(&mut buf).expect("Could not write mapping");
+        let mapping = Mapping::read(buf.as_slice()).expect("Could not read mapping");
+        assert_eq!(mapping.len(), 2);
+
+        let mut importer = Importer::new(&repo, mapping);
+        let created = importer.import(mails).expect("Could not import mails");
+        assert!(created.is_empty());
+
+        let issue = repo
+            .issues()
+            .expect("Could not get issues")
+            .into_iter()
+            .next()
+            .expect("No issue");
+        let mut exported = String::new();
+        export(&issue, &mut exported).expect("Could not export issue");
+        exported.push_str(
+            "From 1 Mon Sep 17 00:00:00 2001\n\
+            From: Jane Doe <jane@example.com>\n\
+            Date: Wed, 15 Nov 2023 10:00:00 +0000\n\
+            Subject: Re: Test issue\n\
+            Message-ID: <3@example.com>\n\
+            References: <1@example.com> <2@example.com>\n\
+            \n\
+            Status: closed\n",
+        );
+        let mails = mail::parse_mbox(&exported).expect("Could not parse mbox");
+        let created = importer.import(mails).expect("Could not import mails");
+        assert_eq!(created.len(), 1);
+        let message = repo.find_commit(created[0]).expect("No such message");
+        assert_eq!(message.parent_ids().into_iter().next(), Some(reply.id()));
+    }
+}
@@ -0,0 +1,465 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Parsing of mails
+//!
+//! This module provides the [Mail] type holding the parts of a mail relevant
+//! for creating messages from it, as well as facilities for splitting
+//! mailboxes into individual mails. Only single part mails are supported. The
+//! bodies of multipart mails are taken as they are.
+
+use std::fmt;
+
+use crate::utils;
+
+/// A single mail
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mail {
+    /// The `Message-ID` of the mail, including angle brackets
+    pub message_id: String,
+    /// The `Message-ID` of the mail this mail replies to, if any
+    pub in_reply_to: Option<String>,
+    /// The `Message-ID`s of the mail's ancestors, starting with the root
+    pub references: Vec<String>,
+    /// Name of the author
+    pub name: String,
+    /// Email address of the author
+    pub email: String,
+    /// Time at which the mail was written in seconds since the Unix epoch
+    pub time: i64,
+    /// Timezone offset of the author in minutes
+    pub offset: i32,
+    /// The mail's subject
+    pub subject: String,
+    /// The mail's decoded body
+    pub body: String,
+}
+
+impl Mail {
+    /// Parse a single mail
+    ///
+    /// The mail may be preceded by a separator line as used in mailboxes.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let text = match text.strip_prefix("From ") {
+            Some(rest) => rest.split_once('\n').map(|(_, t)| t).unwrap_or(""),
+            None => text,
+        };
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut lines = text.split_inclusive('\n');
+        for line in lines.by_ref() {
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with([' ', '\t']) {
+                let (_, value) = headers
+                    .last_mut()
+                    .ok_or_else(|| ParseError::MalformedHeader(line.to_owned()))?;
+                value.push_str(line);
+            } else {
+                let (name, value) = line
+                    .split_once(':')
+                    .ok_or_else(|| ParseError::MalformedHeader(line.to_owned()))?;
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_owned()));
+            }
+        }
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+
+        let message_id = header("message-id")
+            .and_then(|v| message_ids(v).next())
+            .ok_or(ParseError::MissingHeader("Message-ID"))?;
+        let in_reply_to = header("in-reply-to").and_then(|v| message_ids(v).next());
+        let references = header("references")
+            .map(|v| message_ids(v).collect())
+            .unwrap_or_default();
+
+        let from = header("from").ok_or(ParseError::MissingHeader("From"))?;
+        let (name, email) = parse_address(&decode_words(from));
+
+        let date = header("date").ok_or(ParseError::MissingHeader("Date"))?;
+        let (time, offset) =
+            utils::parse_rfc5322(date).ok_or_else(|| ParseError::MalformedDate(date.to_owned()))?;
+
+        let subject = decode_words(header("subject").unwrap_or_default());
+
+        let body: Vec<u8> = lines
+            .flat_map(|l| {
+                let content = l.trim_end_matches(['\r', '\n']);
+                let newline = if l.ends_with('\n') { "\n" } else { "" };
+                [content.as_bytes(), newline.as_bytes()]
+            })
+            .flatten()
+            .copied()
+            .collect();
+        let body = match header("content-transfer-encoding")
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("quoted-printable") => decode_quoted_printable(&body, false),
+            Some("base64") => decode_base64(&body).ok_or(ParseError::MalformedBody)?,
+            _ => body,
+        };
+        let charset = header("content-type")
+            .and_then(|t| {
+                t.split(';')
+                    .filter_map(|p| p.trim().split_once('='))
+                    .find(|(k, _)| k.eq_ignore_ascii_case("charset"))
+                    .map(|(_, v)| v.trim_matches('"'))
+            })
+            .unwrap_or("utf-8");
+        let body = decode_charset(&body, charset);
+
+        Ok(Self {
+            message_id,
+            in_reply_to,
+            references,
+            name,
+            email,
+            time,
+            offset,
+            subject,
+            body,
+        })
+    }
+
+    /// Retrieve the `Message-ID`s of potential parents of this mail
+    ///
+    /// The ids are ordered by preference, starting with the mail this mail
+    /// replies to.
+    pub fn parent_ids(&self) -> impl Iterator<Item = &str> + '_ {
+        self.in_reply_to
+            .iter()
+            .chain(self.references.iter().rev())
+            .map(String::as_str)
+    }
+
+    /// Retrieve the text of a message equivalent to this mail
+    ///
+    /// The message is composed of the mail's subject and body.
+    pub fn message(&self) -> String {
+        let body = self.body.trim_end_matches('\n');
+        if body.trim().is_empty() {
+            format!("{}\n", self.subject)
+        } else {
+            format!("{}\n\n{body}\n", self.subject)
+        }
+    }
+}
+
+/// Split a mailbox into individual mails
+///
+/// Mailboxes are expected in the `mboxrd` format, i.e. each mail starts with a
+/// separator line starting with `From `. Lines quoted with `>` are unquoted.
+/// The empty line preceding each separator is removed.
+pub fn split_mbox(mbox: &str) -> Vec<String> {
+    let mut mails: Vec<String> = Vec::new();
+    for line in mbox.split_inclusive('\n') {
+        if line.starts_with("From ") {
+            if let Some(last) = mails.last_mut() {
+                strip_separator(last);
+            }
+            mails.push(String::new());
+        }
+        let Some(mail) = mails.last_mut() else {
+            continue;
+        };
+        if line.trim_start_matches('>').starts_with("From ") && line.starts_with('>') {
+            mail.push_str(&line[1..]);
+        } else {
+            mail.push_str(line);
+        }
+    }
+    if let Some(last) = mails.last_mut() {
+        strip_separator(last);
+    }
+    mails
+}
+
+/// Parse all mails in a mailbox
+///
+/// See [split_mbox] for details on the expected format.
+pub fn parse_mbox(mbox: &str) -> Result<Vec<Mail>, ParseError> {
+    split_mbox(mbox).iter().map(|m| Mail::parse(m)).collect()
+}
+
+/// Error type for mail parsing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// A required header is missing
+    MissingHeader(&'static str),
+    /// A header line could not be parsed
+    MalformedHeader(String),
+    /// The date of a mail could not be parsed
+    MalformedDate(String),
+    /// The body of a mail could not be decoded
+    MalformedBody,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader(h) => write!(f, "missing header '{h}'"),
+            Self::MalformedHeader(h) => write!(f, "malformed header line '{h}'"),
+            Self::MalformedDate(d) => write!(f, "malformed date '{d}'"),
+            Self::MalformedBody => write!(f, "malformed body"),
+        }
+    }
+}
+
+/// Remove the empty line preceding a separator line from a mail
+fn strip_separator(mail: &mut String) {
+    if mail.ends_with("\n\n") {
+        mail.pop();
+    } else if mail.ends_with("\r\n\r\n") {
+        mail.truncate(mail.len() - 2);
+    }
+}
+
+/// Extract all `Message-ID`s from a header value
+fn message_ids(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split('<')
+        .skip(1)
+        .filter_map(|p| p.split_once('>'))
+        .map(|(id, _)| format!("<{}>", id.trim()))
+}
+
+/// Parse an address into a display name and an email address
+///
+/// If the address does not include a display name, the email address is used
+/// as the name.
+fn parse_address(address: &str) -> (String, String) {
+    let (name, email) = if let Some((name, rest)) = address.rsplit_once('<') {
+        let email = rest.split_once('>').map(|(e, _)| e).unwrap_or(rest);
+        (unquote(name.trim()), email.trim().to_owned())
+    } else if let Some((email, rest)) = address.split_once('(') {
+        let name = rest.rsplit_once(')').map(|(n, _)| n).unwrap_or(rest);
+        (name.trim().to_owned(), email.trim().to_owned())
+    } else {
+        (String::new(), address.trim().to_owned())
+    };
+
+    if name.is_empty() {
+        (email.clone(), email)
+    } else {
+        (name, email)
+    }
+}
+
+/// Remove quotes and escapes from a quoted string
+fn unquote(text: &str) -> String {
+    let Some(text) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) else {
+        return text.to_owned();
+    };
+    let mut res = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => res.extend(chars.next()),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Decode all RFC 2047 encoded words in a header value
+///
+/// Whitespace between adjacent encoded words is removed.
+fn decode_words(value: &str) -> String {
+    let mut res = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, tail) = rest.split_at(start);
+        match decode_word(tail) {
+            Some((decoded, len)) => {
+                if !(after_word && before.trim().is_empty()) {
+                    res.push_str(before);
+                }
+                res.push_str(&decoded);
+                rest = &tail[len..];
+                after_word = true;
+            }
+            None => {
+                res.push_str(before);
+                res.push_str("=?");
+                rest = &tail[2..];
+                after_word = false;
+            }
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
+/// Decode a single encoded word at the start of a text
+///
+/// Returns the decoded text and the length of the encoded word.
+fn decode_word(text: &str) -> Option<(String, usize)> {
+    let inner = text.strip_prefix("=?")?;
+    let (charset, inner) = inner.split_once('?')?;
+    let (encoding, inner) = inner.split_once('?')?;
+    let (data, _) = inner.split_once("?=")?;
+    if data.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let bytes = match encoding {
+        "q" | "Q" => decode_quoted_printable(data.as_bytes(), true),
+        "b" | "B" => decode_base64(data.as_bytes())?,
+        _ => return None,
+    };
+    let len = charset.len() + encoding.len() + data.len() + 6;
+    let charset = charset.split_once('*').map(|(c, _)| c).unwrap_or(charset);
+    Some((decode_charset(&bytes, charset), len))
+}
+
+/// Decode text in the given charset
+///
+/// Any charset other than ISO-8859-1 is treated as UTF-8.
+fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    if charset.eq_ignore_ascii_case("iso-8859-1") || charset.eq_ignore_ascii_case("latin1") {
+        bytes.iter().copied().map(char::from).collect()
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+/// Decode quoted printable data
+///
+/// If `underscore` is set, underscores are decoded as spaces as specified for
+/// encoded words.
+fn decode_quoted_printable(data: &[u8], underscore: bool) -> Vec<u8> {
+    let hex = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+
+    let mut res = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'=' if data.get(i + 1) == Some(&b'\n') => i += 1,
+            b'=' if data[i + 1..].starts_with(b"\r\n") => i += 2,
+            b'=' => match (
+                data.get(i + 1).copied().and_then(hex),
+                data.get(i + 2).copied().and_then(hex),
+            ) {
+                (Some(h), Some(l)) => {
+                    res.push(h << 4 | l);
+                    i += 2;
+                }
+                _ => res.push(b'='),
+            },
+            b'_' if underscore => res.push(b' '),
+            b => res.push(b),
+        }
+        i += 1;
+    }
+    res
+}
+
+/// Decode base64 data, ignoring whitespace
+fn decode_base64(data: &[u8]) -> Option<Vec<u8>> {
+    let value = |b: u8| match b {
+        b'A'..=b'Z' => Some(b - b'A'),
+        b'a'..=b'z' => Some(b - b'a' + 26),
+        b'0'..=b'9' => Some(b - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+
+    let mut res = Vec::with_capacity(data.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for b in data.iter().copied() {
+        if b.is_ascii_whitespace() || b == b'=' {
+            continue;
+        }
+        acc = acc << 6 | u32::from(value(b)?);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let mbox = "From 1 Mon Sep 17 00:00:00 2001\n\
+            From: =?utf-8?q?J=C3=B6hn?= =?utf-8?b?IERvZQ==?= <john@example.com>\n\
+            Date: Tue, 14 Nov 2023 23:13:20 +0100\n\
+            Subject: Test\n issue\n\
+            Message-ID: <1@example.com>\n\
+            \n\
+            >From the beginning.\n\
+            \n\
+            From 2 Mon Sep 17 00:00:00 2001\n\
+            From: \"Doe, Jane\" <jane@example.com>\n\
+            Date: 15 Nov 2023 10:00 GMT\n\
+            Subject: Re: Test issue\n\
+            Message-ID: <2@example.com>\n\
+            In-Reply-To: <1@example.com>\n\
+            References: <0@example.com>\n <1@example.com>\n\
+            Content-Type: text/plain; charset=\"iso-8859-1\"\n\
+            Content-Transfer-Encoding: quoted-printable\n\
+            \n\
+            Gr=FC=DFe, a very long line which had to be wrapped for trans=\n\
+            mission\n\
+            \n";
+        let mails = parse_mbox(mbox).expect("Could not parse mbox");
+        assert_eq!(mails.len(), 2);
+
+        assert_eq!(mails[0].message_id, "<1@example.com>");
+        assert_eq!(mails[0].name, "Jöhn Doe");
+        assert_eq!(mails[0].email, "john@example.com");
+        assert_eq!((mails[0].time, mails[0].offset), (1700000000, 60));
+        assert_eq!(mails[0].parent_ids().next(), None);
+        assert_eq!(mails[0].message(), "Test issue\n\nFrom the beginning.\n");
+
+        assert_eq!(mails[1].name, "Doe, Jane");
+        assert_eq!(
+            mails[1].parent_ids().collect::<Vec<_>>(),
+            ["<1@example.com>", "<1@example.com>", "<0@example.com>"],
+        );
+        assert_eq!(
+            mails[1].body,
+            "Grüße, a very long line which had to be wrapped for transmission\n",
+        );
+
+        assert_eq!(
+            Mail::parse("From: foo@example.com\nDate: 1 Jan 2000 00:00 +0000\n\nBody\n"),
+            Err(ParseError::MissingHeader("Message-ID")),
+        );
+    }
+
+    #[test]
+    fn encoded_words() {
+        let word = "=?utf-8*en?q?J=C3=B6hn?=";
+        assert_eq!(
+            decode_word(&format!("{word} Doe")),
+            Some(("Jöhn".to_owned(), word.len())),
+        );
+        assert_eq!(
+            decode_words("=?utf-8*en?q?J=C3=B6hn?= =?ISO-8859-1*de?Q?D=F6e?= <john@example.com>"),
+            "JöhnDöe <john@example.com>",
+        );
+        assert_eq!(decode_words("=?utf-8?x?foo?= bar"), "=?utf-8?x?foo?= bar");
+    }
+}
@@ -0,0 +1,294 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! In-memory backend
+//!
+//! This module provides implementations of [Database](crate::object::Database),
+//! [Store](crate::reference::Store) and [Traversible](crate::traversal::Traversible)
+//! which keep all objects and references in memory. A [Repository] combines
+//! them, allowing issue workflows to be exercised without a git repository on
+//! disk, e.g. in unit tests.
+//!
+//! Object ids are allocated sequentially. Thus, a commit's id will always be
+//! greater than those of its parents.
+
+use std::fmt;
+
+use crate::error;
+
+mod object;
+mod reference;
+mod traversal;
+
+pub use object::{Blob, Commit, Object, Odb, Signature, Tree, TreeBuilder};
+pub use reference::{Ref, Store};
+pub use traversal::Traversal;
+
+/// In-memory repository
+///
+/// This [crate::Repository] consists of a [Store] for references and an [Odb]
+/// for objects.
+pub type Repository = (Store, Odb);
+
+impl crate::Repository<'_> for Repository {}
+
+/// Object id used by the in-memory backend
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Oid([u8; 20]);
+
+impl std::ops::AddAssign<u8> for Oid {
+    fn add_assign(&mut self, rhs: u8) {
+        self.0.iter_mut().rev().fold(rhs, |c, n| {
+            let [h, l] = (c as u16 + *n as u16).to_be_bytes();
+            *n = l;
+            h
+        });
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.into_iter().try_for_each(|c| write!(f, "{c:02x}"))
+    }
+}
+
+impl std::str::FromStr for Oid {
+    type Err = String;
+
+    fn from_str(mut s: &str) -> Result<Self, Self::Err> {
+        let mut res: [u8; 20] = Default::default();
+        for byte in res.iter_mut() {
+            let (part, rest) = s
+                .split_at_checked(2)
+                .ok_or_else(|| format!("Too short: {s}"))?;
+            *byte = u8::from_str_radix(part, 16).map_err(|_| format!("Not hex: {part}"))?;
+            s = rest;
+        }
+        Ok(Oid(res))
+    }
+}
+
+impl PartialEq<&str> for Oid {
+    fn eq(&self, other: &&str) -> bool {
+        other.parse().map(|i: Self| self == &i).unwrap_or(false)
+    }
+}
+
+/// Error type used by the in-memory backend
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    /// An object or reference is not available
+    NotFound,
+    /// A reference operation conflicts with the current state of the store
+    Conflict,
+    /// An invalid name was used for a tree entry or reference
+    InvalidName,
+}
+
+impl error::InnerError for Error {
+    type Oid = Oid;
+    type RefName = String;
+
+    fn is_not_found(&self) -> bool {
+        *self == Self::NotFound
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "entity not available in memory"),
+            Self::Conflict => write!(f, "conflicting reference state"),
+            Self::InvalidName => write!(f, "invalid name"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oid_incremental_order() {
+        let mut id = Oid::default();
+        id += 1; // id = [1, 0, 0, ...]
+        let one = id;
+        id += 255; // id = [0, 1, 0, ...]
+        assert!(one < id);
+    }
+
+    #[test]
+    fn traversal_first_parents() {
+        use crate::object::{Database, commit::Commit as _};
+        use crate::traversal::{TraversalBuilder, Traversible};
+
+        let repo = Repository::default();
+        let commit = |parents: &[Oid]| {
+            repo.commit_builder(Database::find_commit)
+                .expect("Cannot create commit builder")
+                .with_parents(
+                    parents
+                        .iter()
+                        .map(|p| repo.find_commit(*p).expect("Cannot find parent")),
+                )
+                .build("Test message")
+                .expect("Cannot create commit")
+                .id()
+        };
+
+        let base = commit(&[]);
+        let first = commit(&[base]);
+        let second = commit(&[base]);
+        let merge = commit(&[first, second]);
+
+        let ids: Vec<_> = repo
+            .traversal_builder()
+            .expect("Could not create traversal builder")
+            .with_head(merge)
+            .and_then(TraversalBuilder::build)
+            .expect("Could not build traversal")
+            .collect::<Result<_, _>>()
+            .expect("Could not traverse");
+        assert_eq!(ids, [merge, first, base]);
+
+        let ids: Vec<_> = repo
+            .traversal_builder()
+            .expect("Could not create traversal builder")
+            .with_head(merge)
+            .and_then(|t| t.with_end(second))
+            .and_then(TraversalBuilder::build)
+            .expect("Could not build traversal")
+            .collect::<Result<_, _>>()
+            .expect("Could not traverse");
+        assert_eq!(ids, [merge, first]);
+    }
+
+    #[test]
+    fn attachments() {
+        use crate::object::tree::{Builder, Entry, EntryKind};
+        use crate::object::{Database, commit::Commit as _};
+
+        let repo = Repository::default();
+        let log = repo.write_blob(b"log").expect("Could not write blob");
+        let mut builder = repo
+            .empty_tree_builder()
+            .expect("Could not create tree builder");
+        builder
+            .insert_blob("build.log", log)
+            .expect("Could not insert");
+        let logs = builder.write().expect("Could not write tree");
+
+        let message = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .with_attachment("patch.diff", b"old")
+            .and_then(|b| b.with_attachment("patch.diff", b"new"))
+            .expect("Could not add attachment")
+            .build("Test message")
+            .expect("Cannot create commit");
+        let mut builder = repo
+            .tree_builder(&repo.find_tree(message.tree_id()).expect("No tree"))
+            .expect("Could not create tree builder");
+        builder.insert_tree("logs", logs).expect("Could not insert");
+        let tree = builder.write().expect("Could not write tree");
+        let message = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .with_tree(repo.find_tree(tree).expect("No tree"))
+            .build("Test message")
+            .expect("Cannot create commit");
+
+        let attachments = repo.attachments(&message).expect("Could not list");
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(
+            attachments[0],
+            Entry {
+                name: "logs/build.log".to_owned(),
+                id: log,
+                kind: EntryKind::Blob,
+            }
+        );
+        assert_eq!(attachments[1].name, "patch.diff");
+        assert_eq!(
+            repo.read_blob(attachments[1].id)
+                .expect("Could not read blob"),
+            b"new"
+        );
+        assert!(
+            repo.read_blob(message.id())
+                .expect_err("Read commit as blob")
+                .is_not_found()
+        );
+    }
+
+    #[test]
+    fn commit_signatures() {
+        use crate::object::Database;
+        use crate::object::commit::Commit as _;
+        use crate::object::signature::Signature as _;
+
+        let odb = Odb::default().with_author(Signature::new("Foo Bar", "foo@example.com"));
+        let commit = odb
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .with_committer(Signature::new("Baz", "baz@example.com").with_time(1700000000, -120))
+            .build("Test message")
+            .expect("Cannot create commit");
+
+        let author = commit.author();
+        assert_eq!(author.name(), Ok("Foo Bar"));
+        assert_eq!(author.email(), Ok("foo@example.com"));
+        assert_eq!(author.time(), 0);
+
+        let committer = commit.committer();
+        assert_eq!(committer.name(), Ok("Baz"));
+        assert_eq!(committer.time(), 1700000000);
+        assert_eq!(committer.offset(), -120);
+    }
+
+    #[test]
+    fn store_no_overwrite() {
+        use crate::reference::Store as _;
+
+        let store = Store::default();
+        let mut id = Oid::default();
+        id += 1;
+        store
+            .set_reference("refs/foo".as_ref(), id, false, "")
+            .expect("Could not create reference");
+        let error = store
+            .set_reference("refs/foo".as_ref(), id, false, "")
+            .expect_err("Overwrote reference");
+        assert!(!error.is_not_found());
+        store
+            .set_reference("refs/foo".as_ref(), id, true, "")
+            .expect("Could not overwrite reference");
+    }
+
+    #[test]
+    fn odb_with_objects() {
+        use crate::object::Database;
+
+        let odb = Odb::default();
+        let blob = odb.write_blob(b"foo").expect("Could not write blob");
+        let objects: Vec<_> = odb.ro_objects().iter().cloned().collect();
+
+        let odb = Odb::default().with_objects(objects.into_iter());
+        assert_eq!(odb.read_blob(blob).expect("Could not read blob"), b"foo");
+        let other = odb.write_blob(b"bar").expect("Could not write blob");
+        assert!(blob < other);
+        assert_eq!(odb.read_blob(blob).expect("Could not read blob"), b"foo");
+    }
+
+    #[test]
+    fn oid_roundtrip() {
+        let mut id = Oid::default();
+        id += 42;
+        assert_eq!(id.to_string().parse(), Ok(id));
+    }
+}
@@ -0,0 +1,461 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Object database of the in-memory backend
+
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{self, Hash};
+use std::sync;
+
+use super::{Error, Oid};
+use crate::base::Base;
+use crate::error::{self, ResultExt};
+use crate::object::{Database, commit, signature, tree};
+
+impl<'r, T> Database<'r> for (T, Odb)
+where
+    T: Base<Oid = <Odb as Base>::Oid, InnerError = <Odb as Base>::InnerError>,
+{
+    type Commit = <Odb as Database<'r>>::Commit;
+    type Tree = <Odb as Database<'r>>::Tree;
+    type Signature<'s> = <Odb as Database<'r>>::Signature<'s>;
+    type TreeBuilder = <Odb as Database<'r>>::TreeBuilder;
+
+    fn author(&self) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        self.1.author()
+    }
+
+    fn committer(&self) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        self.1.committer()
+    }
+
+    fn signature(
+        &self,
+        name: &str,
+        email: &str,
+        time: i64,
+        offset: i32,
+    ) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        self.1.signature(name, email, time, offset)
+    }
+
+    fn find_commit(&'r self, oid: Self::Oid) -> error::Result<Self::Commit, Self::InnerError> {
+        self.1.find_commit(oid)
+    }
+
+    fn find_tree(&'r self, oid: Self::Oid) -> error::Result<Self::Tree, Self::InnerError> {
+        self.1.find_tree(oid)
+    }
+
+    fn commit<'s>(
+        &'r self,
+        author: &Self::Signature<'s>,
+        committer: &Self::Signature<'s>,
+        message: &str,
+        tree: &Self::Tree,
+        parents: &[&Self::Commit],
+    ) -> error::Result<Self::Oid, Self::InnerError> {
+        self.1.commit(author, committer, message, tree, parents)
+    }
+
+    fn empty_tree_builder(&'r self) -> error::Result<Self::TreeBuilder, Self::InnerError> {
+        self.1.empty_tree_builder()
+    }
+
+    fn tree_builder(
+        &'r self,
+        tree: &Self::Tree,
+    ) -> error::Result<Self::TreeBuilder, Self::InnerError> {
+        self.1.tree_builder(tree)
+    }
+
+    fn write_blob(&'r self, data: &[u8]) -> error::Result<Self::Oid, Self::InnerError> {
+        self.1.write_blob(data)
+    }
+
+    fn read_blob(&'r self, oid: Self::Oid) -> error::Result<Vec<u8>, Self::InnerError> {
+        self.1.read_blob(oid)
+    }
+
+    fn tree_entries(
+        &'r self,
+        tree: &Self::Tree,
+    ) -> error::Result<Vec<tree::Entry<Self::Oid>>, Self::InnerError> {
+        self.1.tree_entries(tree)
+    }
+}
+
+/// In-memory object database
+///
+/// New objects are assigned sequential ids. The signatures used for new
+/// commits may be configured via [Odb::with_author] and [Odb::with_committer].
+#[derive(Default, Debug)]
+pub struct Odb {
+    objects: sync::RwLock<HashSet<Object>>,
+    id_counter: sync::Mutex<Oid>,
+    author: Signature,
+    committer: Signature,
+}
+
+impl Odb {
+    /// Add the given objects to the database
+    #[cfg(test)]
+    pub(crate) fn with_objects(mut self, objects: impl Iterator<Item = Object>) -> Self {
+        let db = self.objects.get_mut().expect("Could not access objects");
+        db.extend(objects);
+        let id = self.id_counter.get_mut().expect("Could not write oid");
+        *id = db
+            .iter()
+            .map(Borrow::<Oid>::borrow)
+            .max()
+            .cloned()
+            .unwrap_or_default();
+        self
+    }
+
+    /// Set the author used for new commits
+    pub fn with_author(self, author: Signature) -> Self {
+        Self { author, ..self }
+    }
+
+    /// Set the committer used for new commits
+    pub fn with_committer(self, committer: Signature) -> Self {
+        Self { committer, ..self }
+    }
+
+    /// Retrieve read-only access to all objects
+    pub(crate) fn ro_objects(&self) -> sync::RwLockReadGuard<'_, HashSet<Object>> {
+        self.objects.read().expect("Could not read object")
+    }
+
+    fn next_oid(&self) -> Oid {
+        let mut oid = self.id_counter.lock().expect("Could not compute next oid");
+        *oid += 1;
+        *oid
+    }
+}
+
+impl<'r> Database<'r> for Odb {
+    type Commit = Commit;
+    type Tree = Tree;
+    type Signature<'s> = Signature;
+    type TreeBuilder = TreeBuilder<'r>;
+
+    fn author(&self) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        Ok(self.author.clone())
+    }
+
+    fn committer(&self) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        Ok(self.committer.clone())
+    }
+
+    fn signature(
+        &self,
+        name: &str,
+        email: &str,
+        time: i64,
+        offset: i32,
+    ) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        Ok(Signature::new(name, email).with_time(time, offset))
+    }
+
+    fn find_commit(&'r self, oid: Self::Oid) -> error::Result<Self::Commit, Self::InnerError> {
+        self.ro_objects()
+            .get(&oid)
+            .and_then(|o| {
+                if let Object::Commit(c) = o {
+                    Some(c.clone())
+                } else {
+                    None
+                }
+            })
+            .ok_or(Error::NotFound)
+            .wrap_with_kind(error::Kind::CannotGetCommit)
+    }
+
+    fn find_tree(&'r self, oid: Self::Oid) -> error::Result<Self::Tree, Self::InnerError> {
+        self.ro_objects()
+            .get(&oid)
+            .and_then(|o| {
+                if let Object::Tree(t) = o {
+                    Some(t.clone())
+                } else {
+                    None
+                }
+            })
+            .ok_or(Error::NotFound)
+            .wrap_with_kind(error::Kind::CannotGetTree)
+    }
+
+    fn commit<'s>(
+        &'r self,
+        author: &Self::Signature<'s>,
+        committer: &Self::Signature<'s>,
+        message: &str,
+        tree: &Self::Tree,
+        parents: &[&Self::Commit],
+    ) -> error::Result<Self::Oid, Self::InnerError> {
+        let oid = self.next_oid();
+        let commit = Commit {
+            oid,
+            author: author.clone(),
+            committer: committer.clone(),
+            message: message.to_owned(),
+            tree: tree.oid,
+            parents: parents.iter().map(|c| c.oid).collect(),
+        };
+        self.objects
+            .write()
+            .expect("Could not write object")
+            .insert(Object::Commit(commit));
+        Ok(oid)
+    }
+
+    fn empty_tree_builder(&'r self) -> error::Result<Self::TreeBuilder, Self::InnerError> {
+        Ok(TreeBuilder {
+            odb: self,
+            entries: Default::default(),
+        })
+    }
+
+    fn tree_builder(
+        &'r self,
+        tree: &Self::Tree,
+    ) -> error::Result<Self::TreeBuilder, Self::InnerError> {
+        Ok(TreeBuilder {
+            odb: self,
+            entries: tree.entries.clone(),
+        })
+    }
+
+    fn write_blob(&'r self, data: &[u8]) -> error::Result<Self::Oid, Self::InnerError> {
+        let oid = self.next_oid();
+        let blob = Blob {
+            oid,
+            data: data.to_vec(),
+        };
+        self.objects
+            .write()
+            .expect("Could not write object")
+            .insert(Object::Blob(blob));
+        Ok(oid)
+    }
+
+    fn read_blob(&'r self, oid: Self::Oid) -> error::Result<Vec<u8>, Self::InnerError> {
+        self.ro_objects()
+            .get(&oid)
+            .and_then(|o| {
+                if let Object::Blob(b) = o {
+                    Some(b.data.clone())
+                } else {
+                    None
+                }
+            })
+            .ok_or(Error::NotFound)
+            .wrap_with_kind(error::Kind::CannotGetBlob)
+    }
+
+    fn tree_entries(
+        &'r self,
+        tree: &Self::Tree,
+    ) -> error::Result<Vec<tree::Entry<Self::Oid>>, Self::InnerError> {
+        let entries = tree
+            .entries
+            .iter()
+            .map(|(name, (kind, id))| tree::Entry {
+                name: name.clone(),
+                id: *id,
+                kind: *kind,
+            })
+            .collect();
+        Ok(entries)
+    }
+}
+
+impl Base for Odb {
+    type Oid = Oid;
+    type InnerError = Error;
+}
+
+/// Builder for [Tree]s in an [Odb]
+///
+/// The tree is assigned a new id when it is written.
+pub struct TreeBuilder<'r> {
+    odb: &'r Odb,
+    entries: BTreeMap<String, (tree::EntryKind, Oid)>,
+}
+
+impl tree::Builder for TreeBuilder<'_> {
+    type Oid = Oid;
+    type Error = Error;
+
+    fn insert_blob(&mut self, name: &str, blob: Self::Oid) -> Result<(), Self::Error> {
+        tree::check_name(name).map_err(|_| Error::InvalidName)?;
+        self.entries
+            .insert(name.to_owned(), (tree::EntryKind::Blob, blob));
+        Ok(())
+    }
+
+    fn insert_tree(&mut self, name: &str, tree: Self::Oid) -> Result<(), Self::Error> {
+        tree::check_name(name).map_err(|_| Error::InvalidName)?;
+        self.entries
+            .insert(name.to_owned(), (tree::EntryKind::Tree, tree));
+        Ok(())
+    }
+
+    fn write(self) -> Result<Self::Oid, Self::Error> {
+        let oid = self.odb.next_oid();
+        let tree = Tree {
+            oid,
+            entries: self.entries,
+        };
+        self.odb
+            .objects
+            .write()
+            .expect("Could not write object")
+            .insert(Object::Tree(tree));
+        Ok(oid)
+    }
+}
+
+/// An object stored in an [Odb]
+#[derive(Clone, Debug)]
+pub enum Object {
+    Commit(Commit),
+    Tree(Tree),
+    Blob(Blob),
+}
+
+impl Borrow<Oid> for Object {
+    fn borrow(&self) -> &Oid {
+        match self {
+            Self::Commit(c) => &c.oid,
+            Self::Tree(t) => &t.oid,
+            Self::Blob(b) => &b.oid,
+        }
+    }
+}
+
+impl Eq for Object {}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        <Oid as PartialEq>::eq(self.borrow(), other.borrow())
+    }
+}
+
+impl Hash for Object {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: hash::Hasher,
+    {
+        Oid::hash(self.borrow(), state)
+    }
+}
+
+/// A commit stored in an [Odb]
+#[derive(Clone, Debug)]
+pub struct Commit {
+    oid: Oid,
+    author: Signature,
+    committer: Signature,
+    message: String,
+    tree: Oid,
+    parents: Vec<Oid>,
+}
+
+impl commit::Commit for Commit {
+    type Oid = Oid;
+    type Signature<'s> = &'s Signature;
+
+    fn id(&self) -> Self::Oid {
+        self.oid
+    }
+
+    fn author(&self) -> Self::Signature<'_> {
+        &self.author
+    }
+
+    fn committer(&self) -> Self::Signature<'_> {
+        &self.committer
+    }
+
+    fn message(&self) -> Result<&str, std::str::Utf8Error> {
+        Ok(self.message.as_ref())
+    }
+
+    fn parent_ids(&self) -> impl IntoIterator<Item = Self::Oid> + '_ {
+        self.parents.clone()
+    }
+
+    fn tree_id(&self) -> Self::Oid {
+        self.tree
+    }
+}
+
+/// A tree stored in an [Odb]
+#[derive(Clone, Debug)]
+pub struct Tree {
+    oid: Oid,
+    entries: BTreeMap<String, (tree::EntryKind, Oid)>,
+}
+
+/// A blob stored in an [Odb]
+#[derive(Clone, Debug)]
+pub struct Blob {
+    oid: Oid,
+    data: Vec<u8>,
+}
+
+/// A signature used by the in-memory backend
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Signature {
+    name: String,
+    email: String,
+    time: i64,
+    offset: i32,
+}
+
+impl Signature {
+    /// Create a new signature with the given name and email address
+    ///
+    /// The signature's time will be the Unix epoch.
+    pub fn new(name: impl Into<String>, email: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            email: email.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the time and timezone offset in minutes of this signature
+    pub fn with_time(self, time: i64, offset: i32) -> Self {
+        Self {
+            time,
+            offset,
+            ..self
+        }
+    }
+}
+
+impl signature::Signature for Signature {
+    fn name(&self) -> Result<&str, std::str::Utf8Error> {
+        Ok(&self.name)
+    }
+
+    fn email(&self) -> Result<&str, std::str::Utf8Error> {
+        Ok(&self.email)
+    }
+
+    fn time(&self) -> i64 {
+        self.time
+    }
+
+    fn offset(&self) -> i32 {
+        self.offset
+    }
+}
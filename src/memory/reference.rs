@@ -0,0 +1,253 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Reference store of the in-memory backend
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use super::{Error, Oid};
+use crate::base::Base;
+use crate::error::{self, ResultExt};
+use crate::reference::{self, Reference};
+
+impl<'r, T> reference::Store<'r> for (Store, T)
+where
+    T: Base<Oid = <Store as Base>::Oid, InnerError = <Store as Base>::InnerError>,
+{
+    type Reference = <Store as reference::Store<'r>>::Reference;
+    type References = <Store as reference::Store<'r>>::References;
+    type RemoteNames = <Store as reference::Store<'r>>::RemoteNames;
+
+    fn get_reference(
+        &'r self,
+        path: &Path,
+    ) -> error::Result<Option<Self::Reference>, Self::InnerError> {
+        self.0.get_reference(path)
+    }
+
+    fn references(&'r self, prefix: &Path) -> error::Result<Self::References, Self::InnerError> {
+        self.0.references(prefix)
+    }
+
+    fn set_reference(
+        &'r self,
+        name: &Path,
+        target: Self::Oid,
+        overwrite: bool,
+        reflog_msg: &str,
+    ) -> error::Result<Self::Reference, Self::InnerError> {
+        self.0.set_reference(name, target, overwrite, reflog_msg)
+    }
+
+    fn set_reference_matching(
+        &'r self,
+        name: &Path,
+        target: Self::Oid,
+        expected: Option<Self::Oid>,
+        reflog_msg: &str,
+    ) -> error::Result<Self::Reference, Self::InnerError> {
+        self.0
+            .set_reference_matching(name, target, expected, reflog_msg)
+    }
+
+    fn delete_reference(&'r self, name: &Path) -> error::Result<(), Self::InnerError> {
+        self.0.delete_reference(name)
+    }
+
+    fn remote_names(&self) -> error::Result<Self::RemoteNames, Self::InnerError> {
+        self.0.remote_names()
+    }
+}
+
+/// In-memory reference store
+#[derive(Default)]
+pub struct Store {
+    refs: std::sync::Mutex<BTreeSet<Ref>>,
+    remotes: Vec<String>,
+}
+
+impl Store {
+    /// Add the given references to the store
+    pub fn with_refs(mut self, refs: impl IntoIterator<Item = Ref>) -> Self {
+        self.refs
+            .get_mut()
+            .expect("Could not access refs")
+            .extend(refs);
+        self
+    }
+
+    /// Set the names of the remotes reported by this store
+    pub fn with_remotes(self, remotes: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            remotes: remotes.into_iter().collect(),
+            ..self
+        }
+    }
+}
+
+impl<'r> reference::Store<'r> for Store {
+    type Reference = Ref;
+    type References = Vec<Result<Ref, Error>>;
+    type RemoteNames = Vec<String>;
+
+    fn get_reference(
+        &'r self,
+        path: &Path,
+    ) -> error::Result<Option<Self::Reference>, Self::InnerError> {
+        Ok(self
+            .refs
+            .lock()
+            .expect("Could not access refs")
+            .get(path)
+            .cloned())
+    }
+
+    fn references(&'r self, prefix: &Path) -> error::Result<Self::References, Self::InnerError> {
+        let res = self
+            .refs
+            .lock()
+            .expect("Could not access refs")
+            .iter()
+            .filter(|r| r.name.starts_with(prefix))
+            .cloned()
+            .map(Ok)
+            .collect();
+        Ok(res)
+    }
+
+    fn set_reference(
+        &'r self,
+        name: &Path,
+        target: Self::Oid,
+        overwrite: bool,
+        _reflog_msg: &str,
+    ) -> error::Result<Self::Reference, Self::InnerError> {
+        let new = Ref::from(name.to_owned()).with_target(target);
+        let mut refs = self.refs.lock().expect("Could not access refs");
+        if overwrite {
+            refs.replace(new.clone());
+        } else if !refs.insert(new.clone()) {
+            return Err(Error::Conflict)
+                .wrap_with(|| error::Kind::CannotSetReference(name.display().to_string()));
+        }
+
+        Ok(new)
+    }
+
+    fn set_reference_matching(
+        &'r self,
+        name: &Path,
+        target: Self::Oid,
+        expected: Option<Self::Oid>,
+        _reflog_msg: &str,
+    ) -> error::Result<Self::Reference, Self::InnerError> {
+        let new = Ref::from(name.to_owned()).with_target(target);
+        let mut refs = self.refs.lock().expect("Could not access refs");
+        if refs.get(name).map(|r| r.target) != expected.map(Some) {
+            return Err(Error::Conflict)
+                .wrap_with(|| error::Kind::CannotSetReference(name.display().to_string()));
+        }
+        refs.replace(new.clone());
+        Ok(new)
+    }
+
+    fn delete_reference(&'r self, name: &Path) -> error::Result<(), Self::InnerError> {
+        let removed = self
+            .refs
+            .lock()
+            .expect("Could not access refs")
+            .remove(name);
+        if removed {
+            Ok(())
+        } else {
+            Err(Error::Conflict)
+                .wrap_with(|| error::Kind::CannotDeleteReference(name.display().to_string()))
+        }
+    }
+
+    fn remote_names(&self) -> error::Result<Self::RemoteNames, Self::InnerError> {
+        Ok(self.remotes.clone())
+    }
+}
+
+impl Base for Store {
+    type Oid = Oid;
+    type InnerError = Error;
+}
+
+/// A reference in a [Store]
+#[derive(Clone, Debug)]
+pub struct Ref {
+    name: PathBuf,
+    target: Option<Oid>,
+}
+
+impl Ref {
+    /// Set the target of this reference
+    pub fn with_target(self, target: Oid) -> Self {
+        Self {
+            target: Some(target),
+            ..self
+        }
+    }
+}
+
+impl From<PathBuf> for Ref {
+    fn from(name: PathBuf) -> Self {
+        Self { name, target: None }
+    }
+}
+
+impl From<&str> for Ref {
+    fn from(path: &str) -> Self {
+        PathBuf::from(path).into()
+    }
+}
+
+impl std::borrow::Borrow<Path> for Ref {
+    fn borrow(&self) -> &Path {
+        &self.name
+    }
+}
+
+impl Eq for Ref {}
+
+impl PartialEq for Ref {
+    fn eq(&self, other: &Self) -> bool {
+        PartialEq::eq(&self.name, &other.name)
+    }
+}
+
+impl Ord for Ref {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        Ord::cmp(&self.name, &other.name)
+    }
+}
+
+impl PartialOrd for Ref {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Reference for Ref {
+    type Name = str;
+    type Oid = Oid;
+    type Error = Error;
+
+    fn name(&self) -> Result<&Self::Name, Self::Error> {
+        self.name.to_str().ok_or(Error::InvalidName)
+    }
+
+    fn as_path(&self) -> Result<&Path, Self::Error> {
+        Ok(self.name.as_ref())
+    }
+
+    fn target(&self) -> Option<Self::Oid> {
+        self.target
+    }
+}
@@ -0,0 +1,119 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Traversal for the in-memory backend
+
+use std::collections::{BinaryHeap, HashSet};
+use std::sync;
+
+use super::{Error, Object, Odb, Oid};
+use crate::base::Base;
+use crate::error;
+use crate::object::commit::Commit;
+use crate::traversal::{TraversalBuilder, Traversible};
+
+impl<'t, T> Traversible<'t> for (T, Odb)
+where
+    T: Base<Oid = <Odb as Base>::Oid, InnerError = <Odb as Base>::InnerError>,
+{
+    type TraversalBuilder = <Odb as Traversible<'t>>::TraversalBuilder;
+
+    fn traversal_builder(&'t self) -> error::Result<Self::TraversalBuilder, Self::InnerError> {
+        self.1.traversal_builder()
+    }
+}
+
+impl<'t> Traversible<'t> for Odb {
+    type TraversalBuilder = Traversal<'t>;
+
+    fn traversal_builder(&'t self) -> error::Result<Self::TraversalBuilder, Self::InnerError> {
+        Ok(Traversal {
+            db: self.ro_objects(),
+            heads: Default::default(),
+            ends: Default::default(),
+        })
+    }
+}
+
+/// Traversal over commits in an [Odb]
+///
+/// Like the traversals of other backends, this traversal only follows first
+/// parents. Ends hide both themselves and all of their ancestors. Commits are
+/// yielded in descending order of their ids, which is a topological order for
+/// commits created through an [Odb].
+pub struct Traversal<'t> {
+    db: sync::RwLockReadGuard<'t, HashSet<Object>>,
+    heads: BinaryHeap<Oid>,
+    ends: HashSet<Oid>,
+}
+
+impl Traversal<'_> {
+    /// Retrieve the ids of the parents of a commit
+    fn parents(&self, id: &Oid) -> Result<Vec<Oid>, Error> {
+        match self.db.get(id) {
+            Some(Object::Commit(commit)) => Ok(commit.parent_ids().into_iter().collect()),
+            _ => Err(Error::NotFound),
+        }
+    }
+}
+
+impl TraversalBuilder for Traversal<'_> {
+    type Oid = Oid;
+    type Error = Error;
+    type Iter = Self;
+    type BuildError = Error;
+
+    fn with_heads(
+        mut self,
+        heads: impl IntoIterator<Item = impl Into<Self::Oid>>,
+    ) -> Result<Self, Self::BuildError> {
+        self.heads.extend(heads.into_iter().map(Into::into));
+        Ok(self)
+    }
+
+    fn with_ends(
+        mut self,
+        ends: impl IntoIterator<Item = impl Into<Self::Oid>>,
+    ) -> Result<Self, Self::BuildError> {
+        self.ends.extend(ends.into_iter().map(Into::into));
+        Ok(self)
+    }
+
+    fn build(mut self) -> Result<Self::Iter, Self::BuildError> {
+        // Ends hide all their ancestors, not only the first parents.
+        let mut pending: Vec<_> = self.ends.iter().copied().collect();
+        while let Some(id) = pending.pop() {
+            let parents = self.parents(&id).unwrap_or_default();
+            pending.extend(parents.into_iter().filter(|p| self.ends.insert(*p)));
+        }
+        Ok(self)
+    }
+}
+
+impl Iterator for Traversal<'_> {
+    type Item = Result<Oid, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = loop {
+            let id = self.heads.pop()?;
+            if !self.ends.contains(&id) {
+                break id;
+            }
+        };
+
+        let parent = match self.parents(&id) {
+            Ok(parents) => parents.into_iter().next(),
+            Err(e) => return Some(Err(e)),
+        };
+        self.heads.extend(parent.filter(|p| !self.ends.contains(p)));
+
+        // The same commit may be the parent of multiple commits we've already
+        // yielded. We don't check for duplicates when pushing them.
+        self.heads.retain(|c| c < &id);
+
+        Some(Ok(id))
+    }
+}
@@ -0,0 +1,438 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Message related functionality
+//!
+//! This module provides the [Message] extension trait for [Commit]s as well as
+//! the [Parts] type for splitting a message into its subject, its body and its
+//! trailers.
+//!
+//! Trailers are extracted following the rules of `git interpret-trailers`: the
+//! trailer block is the last paragraph of a message, but never its subject.
+//! Every line in this paragraph must either be a trailer in the form of
+//! `Key: value` or a continuation line starting with whitespace. Comment lines,
+//! i.e. lines starting with `#`, are ignored.
+//!
+//! For creating messages, this module provides the [Composer].
+
+use std::fmt;
+use std::str::Utf8Error;
+use std::vec;
+
+use crate::error;
+use crate::object::commit::Commit;
+use crate::trailer::{Trailer, TrailerKey, TrailerValue};
+
+/// Extension trait for accessing the parts of a git-dit message
+///
+/// This trait is implemented for all [Commit]s.
+pub trait Message {
+    /// Retrieve the message split into its parts
+    fn parts(&self) -> Result<Parts<'_>, Utf8Error>;
+
+    /// Retrieve the subject of the message
+    fn subject(&self) -> Result<&str, Utf8Error> {
+        self.parts().map(|p| p.subject)
+    }
+
+    /// Retrieve the paragraphs of the message's body
+    ///
+    /// The body does not include the subject or the trailer block.
+    fn body_paragraphs(&self) -> Result<vec::IntoIter<&str>, Utf8Error> {
+        self.parts().map(|p| p.body.into_iter())
+    }
+
+    /// Retrieve the trailers of the message
+    fn trailers(&self) -> Result<vec::IntoIter<Trailer>, Utf8Error> {
+        self.parts().map(|p| p.trailers.into_iter())
+    }
+}
+
+impl<C: Commit + ?Sized> Message for C {
+    fn parts(&self) -> Result<Parts<'_>, Utf8Error> {
+        self.message().map(Parts::parse)
+    }
+}
+
+/// A message split into its parts
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Parts<'m> {
+    /// The subject, i.e. the first paragraph
+    pub subject: &'m str,
+    /// The paragraphs between the subject and the trailer block
+    pub body: Vec<&'m str>,
+    /// The trailers found in the trailer block
+    pub trailers: Vec<Trailer>,
+}
+
+impl<'m> Parts<'m> {
+    /// Split a message into its parts
+    pub fn parse(text: &'m str) -> Self {
+        let mut body = paragraphs(text);
+        if body.is_empty() {
+            return Default::default();
+        }
+        let subject = body.remove(0);
+
+        let trailers = body.last().and_then(|p| trailer_block(p));
+        if trailers.is_some() {
+            body.pop();
+        }
+
+        Self {
+            subject,
+            body,
+            trailers: trailers.unwrap_or_default(),
+        }
+    }
+}
+
+impl<'m> From<&'m str> for Parts<'m> {
+    fn from(text: &'m str) -> Self {
+        Self::parse(text)
+    }
+}
+
+/// Composer for well-formed messages
+///
+/// This type assembles a message from a subject, body paragraphs and trailers.
+/// Its [fmt::Display] implementation renders the message with the subject on
+/// the first line, separates all paragraphs and the trailer block by a blank
+/// line and folds multi line trailer values into continuation lines. Thus, a
+/// message composed from [Parts] yields the same [Parts] when parsed.
+///
+/// Trailers with a key which is not a valid [TrailerKey] or with a value
+/// containing a blank line could not be recovered from the message. Use
+/// [Composer::render] for rendering a message while rejecting such trailers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Composer {
+    subject: String,
+    body: Vec<String>,
+    trailers: Vec<Trailer>,
+}
+
+impl Composer {
+    /// Create a new composer for a message with the given subject
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a body paragraph
+    ///
+    /// The text may also consist of multiple paragraphs.
+    pub fn with_body(mut self, text: impl Into<String>) -> Self {
+        self.body.push(text.into());
+        self
+    }
+
+    /// Add a single trailer
+    pub fn with_trailer(mut self, trailer: Trailer) -> Self {
+        self.trailers.push(trailer);
+        self
+    }
+
+    /// Add trailers
+    pub fn with_trailers(mut self, trailers: impl IntoIterator<Item = Trailer>) -> Self {
+        self.trailers.extend(trailers);
+        self
+    }
+
+    /// Render the message
+    ///
+    /// In contrast to the [fmt::Display] implementation, this fn fails for
+    /// trailers which could not be recovered from the rendered message.
+    pub fn render(&self) -> Result<String, MalformedTrailer> {
+        let malformed = self.trailers.iter().find(|t| {
+            t.key.as_ref().parse::<TrailerKey>().is_err()
+                || t.value
+                    .to_string()
+                    .lines()
+                    .skip(1)
+                    .any(|l| l.trim().is_empty())
+        });
+        match malformed {
+            Some(trailer) => Err(MalformedTrailer(trailer.clone())),
+            None => Ok(self.to_string()),
+        }
+    }
+}
+
+impl TryFrom<Composer> for String {
+    type Error = MalformedTrailer;
+
+    fn try_from(composer: Composer) -> Result<Self, Self::Error> {
+        composer.render()
+    }
+}
+
+impl From<Parts<'_>> for Composer {
+    fn from(parts: Parts<'_>) -> Self {
+        Self::new(parts.subject)
+            .with_trailers(parts.trailers)
+            .with_body(parts.body.join("\n\n"))
+    }
+}
+
+impl fmt::Display for Composer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subject: Vec<_> = self.subject.split_whitespace().collect();
+        writeln!(f, "{}", subject.join(" "))?;
+
+        for paragraph in self.body.iter().map(|p| p.trim()) {
+            if !paragraph.is_empty() {
+                write!(f, "\n{paragraph}\n")?;
+            }
+        }
+
+        if !self.trailers.is_empty() {
+            writeln!(f)?;
+        }
+        for trailer in &self.trailers {
+            let value = trailer.value.to_string();
+            let mut lines = value.lines();
+            write!(f, "{}: {}", trailer.key, lines.next().unwrap_or_default())?;
+            lines.try_for_each(|l| write!(f, "\n {l}"))?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error indicating a trailer which cannot be represented in a message
+#[derive(Clone, Debug, PartialEq)]
+pub struct MalformedTrailer(pub Trailer);
+
+impl std::error::Error for MalformedTrailer {}
+
+impl fmt::Display for MalformedTrailer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "trailer cannot be represented in a message: {}", self.0)
+    }
+}
+
+impl<I: error::InnerError> From<MalformedTrailer> for error::Kind<I> {
+    fn from(err: MalformedTrailer) -> Self {
+        Self::TrailerFormatError(err.0.to_string())
+    }
+}
+
+/// Split a text into paragraphs
+///
+/// Paragraphs are separated by one or more blank lines.
+fn paragraphs(text: &str) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in text.split('\n') {
+        let end = offset + line.len();
+        if line.trim().is_empty() {
+            res.extend(current.take().map(|(s, e)| text[s..e].trim_end()));
+        } else {
+            current = Some((current.map(|(s, _)| s).unwrap_or(offset), end));
+        }
+        offset = end + 1;
+    }
+    res.extend(current.map(|(s, e)| text[s..e].trim_end()));
+    res
+}
+
+/// Extract the trailers from a paragraph
+///
+/// This function returns `None` if the paragraph is not a trailer block.
+fn trailer_block(paragraph: &str) -> Option<Vec<Trailer>> {
+    let mut trailers: Vec<Trailer> = Vec::new();
+    for line in paragraph.lines().filter(|l| !l.starts_with('#')) {
+        if line.starts_with(char::is_whitespace) {
+            let value = &mut trailers.last_mut()?.value;
+            value.append("\n");
+            value.append(line.trim());
+        } else {
+            trailers.push(trailer(line)?);
+        }
+    }
+    Some(trailers).filter(|t| !t.is_empty())
+}
+
+/// Parse a single trailer line
+fn trailer(line: &str) -> Option<Trailer> {
+    let (key, value) = line.split_once(':')?;
+    Some(Trailer {
+        key: key.trim_end().parse().ok()?,
+        value: TrailerValue::from_slice(value.trim()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subject_only() {
+        let parts = Parts::parse("Foo bar\n");
+        assert_eq!(parts.subject, "Foo bar");
+        assert!(parts.body.is_empty());
+        assert!(parts.trailers.is_empty());
+    }
+
+    #[test]
+    fn subject_not_trailers() {
+        let parts = Parts::parse("Dit-status: closed\n");
+        assert_eq!(parts.subject, "Dit-status: closed");
+        assert!(parts.trailers.is_empty());
+    }
+
+    #[test]
+    fn full_message() {
+        let parts = Parts::parse(
+            "Foo bar\n\nSome text\nspanning lines\n\n  \nMore text\n\n\
+             Dit-status: closed\n# A comment\nDit-type : bug\nFoo-bar: 3\n",
+        );
+        assert_eq!(parts.subject, "Foo bar");
+        assert_eq!(parts.body, ["Some text\nspanning lines", "More text"]);
+        assert_eq!(
+            parts.trailers,
+            [
+                Trailer::new("Dit-status", "closed"),
+                Trailer::new("Dit-type", "bug"),
+                Trailer::new("Foo-bar", "3"),
+            ]
+        );
+        assert_eq!(parts.trailers[2].value, TrailerValue::Int(3));
+    }
+
+    #[test]
+    fn continuation_lines() {
+        let parts = Parts::parse("Foo\n\nDit-note: first\n  second\n\tthird\nDit-type: bug");
+        assert_eq!(
+            parts.trailers,
+            [
+                Trailer::new("Dit-note", "first\nsecond\nthird"),
+                Trailer::new("Dit-type", "bug"),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_trailer_block() {
+        let parts = Parts::parse("Foo\n\nDit-status: closed\nNot a trailer\n");
+        assert_eq!(parts.body, ["Dit-status: closed\nNot a trailer"]);
+        assert!(parts.trailers.is_empty());
+
+        let parts = Parts::parse("Foo\n\n  leading continuation\nDit-status: closed\n");
+        assert!(parts.trailers.is_empty());
+    }
+
+    #[test]
+    fn crlf() {
+        let parts = Parts::parse("Foo\r\n\r\nBar\r\n\r\nDit-status: closed\r\n");
+        assert_eq!(parts.subject, "Foo");
+        assert_eq!(parts.body, ["Bar"]);
+        assert_eq!(parts.trailers, [Trailer::new("Dit-status", "closed")]);
+    }
+
+    #[test]
+    fn compose() {
+        let composer = Composer::new("Foo\nbar ")
+            .with_body("Some text\nspanning lines\n\nMore text\n")
+            .with_body("  ")
+            .with_trailer(Trailer::new("Dit-status", "open"))
+            .with_trailers([Trailer::new("Foo-bar", "3")]);
+        assert_eq!(
+            composer.to_string(),
+            "Foo bar\n\nSome text\nspanning lines\n\nMore text\n\n\
+             Dit-status: open\nFoo-bar: 3\n",
+        );
+
+        assert_eq!(Composer::new("Foo").to_string(), "Foo\n");
+        assert_eq!(
+            Composer::new("Foo")
+                .with_trailer(Trailer::new("Dit-type", "bug"))
+                .to_string(),
+            "Foo\n\nDit-type: bug\n",
+        );
+    }
+
+    #[test]
+    fn compose_roundtrip() {
+        let text = "Foo\n\nBar\n\nBaz\n\nDit-note: first\n second\nDit-type: bug\n";
+        let parts = Parts::parse(text);
+        let composed = Composer::from(parts.clone()).to_string();
+        assert_eq!(composed, text);
+        assert_eq!(Parts::parse(&composed), parts);
+    }
+
+    #[test]
+    fn compose_multi_line_trailer() {
+        let composer = Composer::new("Foo")
+            .with_body("Bar")
+            .with_trailer(Trailer::new("Dit-note", "first\nsecond\nthird"))
+            .with_trailer(Trailer::new("Dit-type", "bug"));
+        let text = composer.to_string();
+        assert_eq!(
+            text,
+            "Foo\n\nBar\n\nDit-note: first\n second\n third\nDit-type: bug\n",
+        );
+        let parts = Parts::parse(&text);
+        assert_eq!(
+            parts.trailers,
+            [
+                Trailer::new("Dit-note", "first\nsecond\nthird"),
+                Trailer::new("Dit-type", "bug"),
+            ],
+        );
+        assert_eq!(Composer::from(parts).to_string(), text);
+    }
+
+    #[test]
+    fn compose_malformed_trailer() {
+        let compose = |trailer| Composer::new("Foo").with_trailer(trailer).render();
+        assert!(compose(Trailer::new("Dit-note", "first\n\nsecond")).is_err());
+        assert!(compose(Trailer::new("Dit-note", "first\n \nsecond")).is_err());
+        assert!(compose(Trailer::new("Dit note", "first")).is_err());
+        assert!(compose(Trailer::new("", "first")).is_err());
+        assert!(compose(Trailer::new("Dit-note", "")).is_ok());
+        assert_eq!(
+            Composer::new("Foo")
+                .with_trailer(Trailer::new("Dit note", "first"))
+                .to_string(),
+            "Foo\n\nDit note: first\n",
+        );
+    }
+
+    #[test]
+    fn commit_extension() {
+        use crate::memory;
+        use crate::object::Database;
+
+        let repo = memory::Repository::default();
+        let commit = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .build(
+                Composer::new("Foo")
+                    .with_body("Bar")
+                    .with_trailer(Trailer::new("Dit-type", "bug")),
+            )
+            .expect("Cannot create commit");
+        assert_eq!(commit.subject(), Ok("Foo"));
+        assert_eq!(
+            commit
+                .trailers()
+                .expect("Cannot parse message")
+                .collect::<Vec<_>>(),
+            [Trailer::new("Dit-type", "bug")]
+        );
+
+        let malformed = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .build(Composer::new("Foo").with_trailer(Trailer::new("Dit type", "bug")));
+        assert!(malformed.is_err());
+    }
+}
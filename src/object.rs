@@ -10,6 +10,7 @@ use crate::base::Base;
 use crate::error::{self, ResultExt};
 
 pub mod commit;
+pub mod signature;
 pub mod tree;
 
 use commit::Commit;
@@ -23,7 +24,7 @@ pub trait Database<'r>: Base {
     type Tree;
 
     /// Type for representing signautres
-    type Signature<'s>;
+    type Signature<'s>: signature::Signature;
 
     /// A builder for trees
     type TreeBuilder: tree::Builder<Oid = Self::Oid, Error: Into<Self::InnerError>>;
@@ -34,6 +35,18 @@ pub trait Database<'r>: Base {
     /// Retrieve the signature to use for committer
     fn committer(&self) -> error::Result<Self::Signature<'_>, Self::InnerError>;
 
+    /// Create a signature with the given name, email address and time
+    ///
+    /// The time is given in seconds since the Unix epoch, the timezone offset
+    /// in minutes.
+    fn signature(
+        &self,
+        name: &str,
+        email: &str,
+        time: i64,
+        offset: i32,
+    ) -> error::Result<Self::Signature<'_>, Self::InnerError>;
+
     /// Retrieve a specific commit
     fn find_commit(&'r self, oid: Self::Oid) -> error::Result<Self::Commit, Self::InnerError>;
 
@@ -83,6 +96,44 @@ pub trait Database<'r>: Base {
         &'r self,
         tree: &Self::Tree,
     ) -> error::Result<Self::TreeBuilder, Self::InnerError>;
+
+    /// Write a blob with the given contents
+    fn write_blob(&'r self, data: &[u8]) -> error::Result<Self::Oid, Self::InnerError>;
+
+    /// Retrieve the contents of a specific blob
+    fn read_blob(&'r self, oid: Self::Oid) -> error::Result<Vec<u8>, Self::InnerError>;
+
+    /// Retrieve the entries of a tree
+    ///
+    /// Only entries referring to blobs and subtrees are reported.
+    fn tree_entries(
+        &'r self,
+        tree: &Self::Tree,
+    ) -> error::Result<Vec<tree::Entry<Self::Oid>>, Self::InnerError>;
+
+    /// Retrieve the attachments of a message
+    ///
+    /// Attachments are the blobs in a message's tree, including those in
+    /// subtrees. The name of each [tree::Entry] returned is the path of the
+    /// blob relative to the message's tree, with components separated by `/`.
+    fn attachments(
+        &'r self,
+        message: &Self::Commit,
+    ) -> error::Result<Vec<tree::Entry<Self::Oid>>, Self::InnerError> {
+        let mut res = Vec::new();
+        let mut pending = vec![(String::new(), message.tree_id())];
+        while let Some((prefix, tree)) = pending.pop() {
+            for entry in self.tree_entries(&self.find_tree(tree)?)? {
+                let name = format!("{prefix}{}", entry.name);
+                match entry.kind {
+                    tree::EntryKind::Blob => res.push(tree::Entry { name, ..entry }),
+                    tree::EntryKind::Tree => pending.push((name + "/", entry.id)),
+                }
+            }
+        }
+        res.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(res)
+    }
 }
 
 #[cfg(feature = "git2")]
@@ -101,6 +152,17 @@ impl<'r> Database<'r> for git2::Repository {
         self.author()
     }
 
+    fn signature(
+        &self,
+        name: &str,
+        email: &str,
+        time: i64,
+        offset: i32,
+    ) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        git2::Signature::new(name, email, &git2::Time::new(time, offset))
+            .wrap_with_kind(error::Kind::CannotGetSignature)
+    }
+
     fn find_commit(&'r self, oid: Self::Oid) -> error::Result<Self::Commit, Self::InnerError> {
         git2::Repository::find_commit(self, oid).wrap_with_kind(error::Kind::CannotGetCommit)
     }
@@ -134,7 +196,296 @@ impl<'r> Database<'r> for git2::Repository {
         self.treebuilder(Some(tree))
             .wrap_with_kind(error::Kind::CannotCreateTreeBuilder)
     }
+
+    fn write_blob(&'r self, data: &[u8]) -> error::Result<Self::Oid, Self::InnerError> {
+        self.blob(data).wrap_with_kind(error::Kind::CannotWriteBlob)
+    }
+
+    fn read_blob(&'r self, oid: Self::Oid) -> error::Result<Vec<u8>, Self::InnerError> {
+        self.find_blob(oid)
+            .map(|b| b.content().to_vec())
+            .wrap_with_kind(error::Kind::CannotGetBlob)
+    }
+
+    fn tree_entries(
+        &'r self,
+        tree: &Self::Tree,
+    ) -> error::Result<Vec<tree::Entry<Self::Oid>>, Self::InnerError> {
+        tree.iter()
+            .filter_map(|e| {
+                let kind = match e.kind()? {
+                    git2::ObjectType::Blob => tree::EntryKind::Blob,
+                    git2::ObjectType::Tree => tree::EntryKind::Tree,
+                    _ => return None,
+                };
+                let name = std::str::from_utf8(e.name_bytes())
+                    .map(ToOwned::to_owned)
+                    .wrap_with_kind(error::Kind::CannotGetTree);
+                Some(name.map(|name| tree::Entry {
+                    name,
+                    id: e.id(),
+                    kind,
+                }))
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "gix")]
+impl<'r> Database<'r> for gix::Repository {
+    type Commit = commit::GixCommit;
+    type Tree = gix::Tree<'r>;
+    type Signature<'s> = gix::actor::Signature;
+    type TreeBuilder = tree::GixTreeBuilder<'r>;
+
+    fn author(&self) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        gix::Repository::author(self)
+            .ok_or(error::Kind::CannotGetSignature)?
+            .map_err(error::GixError::new)
+            .and_then(|s| s.to_owned().map_err(error::GixError::new))
+            .wrap_with_kind(error::Kind::CannotGetSignature)
+    }
+
+    fn committer(&self) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        gix::Repository::committer(self)
+            .ok_or(error::Kind::CannotGetSignature)?
+            .map_err(error::GixError::new)
+            .and_then(|s| s.to_owned().map_err(error::GixError::new))
+            .wrap_with_kind(error::Kind::CannotGetSignature)
+    }
+
+    fn signature(
+        &self,
+        name: &str,
+        email: &str,
+        time: i64,
+        offset: i32,
+    ) -> error::Result<Self::Signature<'_>, Self::InnerError> {
+        Ok(gix::actor::Signature {
+            name: name.into(),
+            email: email.into(),
+            time: gix::date::Time::new(time, offset * 60),
+        })
+    }
+
+    fn find_commit(&'r self, oid: Self::Oid) -> error::Result<Self::Commit, Self::InnerError> {
+        gix::Repository::find_commit(self, oid)
+            .map_err(error::GixError::new)
+            .and_then(|c| c.try_into().map_err(error::GixError::new))
+            .wrap_with_kind(error::Kind::CannotGetCommit)
+    }
+
+    fn find_tree(&'r self, oid: Self::Oid) -> error::Result<Self::Tree, Self::InnerError> {
+        gix::Repository::find_tree(self, oid)
+            .map_err(error::GixError::new)
+            .wrap_with_kind(error::Kind::CannotGetTree)
+    }
+
+    fn commit<'s>(
+        &'r self,
+        author: &Self::Signature<'s>,
+        committer: &Self::Signature<'s>,
+        message: &str,
+        tree: &Self::Tree,
+        parents: &[&Self::Commit],
+    ) -> error::Result<Self::Oid, Self::InnerError> {
+        use commit::Commit;
+
+        let commit = gix::objs::Commit {
+            tree: tree.id,
+            parents: parents.iter().map(|p| p.id()).collect(),
+            author: author.clone(),
+            committer: committer.clone(),
+            encoding: None,
+            message: message.into(),
+            extra_headers: Default::default(),
+        };
+        self.write_object(&commit)
+            .map(gix::Id::detach)
+            .map_err(error::GixError::new)
+            .wrap_with_kind(error::Kind::CannotCreateMessage)
+    }
+
+    fn empty_tree_builder(&'r self) -> error::Result<Self::TreeBuilder, Self::InnerError> {
+        Ok(tree::GixTreeBuilder::new(self, gix::objs::Tree::empty()))
+    }
+
+    fn tree_builder(
+        &'r self,
+        tree: &Self::Tree,
+    ) -> error::Result<Self::TreeBuilder, Self::InnerError> {
+        let tree = tree
+            .decode()
+            .map_err(error::GixError::new)
+            .wrap_with_kind(error::Kind::CannotCreateTreeBuilder)?;
+        Ok(tree::GixTreeBuilder::new(self, tree.into()))
+    }
+
+    fn write_blob(&'r self, data: &[u8]) -> error::Result<Self::Oid, Self::InnerError> {
+        gix::Repository::write_blob(self, data)
+            .map(gix::Id::detach)
+            .map_err(error::GixError::new)
+            .wrap_with_kind(error::Kind::CannotWriteBlob)
+    }
+
+    fn read_blob(&'r self, oid: Self::Oid) -> error::Result<Vec<u8>, Self::InnerError> {
+        gix::Repository::find_blob(self, oid)
+            .map(|mut b| b.take_data())
+            .map_err(error::GixError::new)
+            .wrap_with_kind(error::Kind::CannotGetBlob)
+    }
+
+    fn tree_entries(
+        &'r self,
+        tree: &Self::Tree,
+    ) -> error::Result<Vec<tree::Entry<Self::Oid>>, Self::InnerError> {
+        use gix::objs::tree::EntryKind;
+
+        let decoded = tree
+            .decode()
+            .map_err(error::GixError::new)
+            .wrap_with_kind(error::Kind::CannotGetTree)?;
+        decoded
+            .entries
+            .iter()
+            .filter_map(|e| {
+                let kind = match e.mode.kind() {
+                    EntryKind::Blob | EntryKind::BlobExecutable => tree::EntryKind::Blob,
+                    EntryKind::Tree => tree::EntryKind::Tree,
+                    _ => return None,
+                };
+                let name = std::str::from_utf8(e.filename.as_ref())
+                    .map(ToOwned::to_owned)
+                    .wrap_with_kind(error::Kind::CannotGetTree);
+                Some(name.map(|name| tree::Entry {
+                    name,
+                    id: e.oid.to_owned(),
+                    kind,
+                }))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
-pub mod tests;
+mod tests {
+    use super::*;
+
+    /// Check that tree builders of a repository reject invalid entry names
+    fn reject_invalid_names<'r, R: Database<'r>>(repo: &'r R) {
+        use tree::Builder;
+
+        let blob = Database::write_blob(repo, b"data").expect("Could not write blob");
+        let mut builder = repo
+            .empty_tree_builder()
+            .expect("Could not create tree builder");
+        for name in ["a/b", "", "a\0b", ".", ".."] {
+            assert!(builder.insert_blob(name, blob.clone()).is_err());
+        }
+        builder
+            .insert_blob("a", blob)
+            .map_err(Into::<R::InnerError>::into)
+            .expect("Could not insert");
+        let tree = builder
+            .write()
+            .map_err(Into::<R::InnerError>::into)
+            .expect("Could not write tree");
+
+        let mut builder = repo
+            .empty_tree_builder()
+            .expect("Could not create tree builder");
+        for name in ["a/b", "", "a\0b"] {
+            assert!(builder.insert_tree(name, tree.clone()).is_err());
+        }
+    }
+
+    #[test]
+    fn memory_tree_names() {
+        reject_invalid_names(&crate::memory::Repository::default());
+    }
+
+    #[cfg(feature = "git2")]
+    #[test]
+    fn git2_tree_names() {
+        let (_dir, repo) = crate::base::tests::git2_repository();
+        reject_invalid_names(&repo);
+    }
+
+    #[cfg(feature = "gix")]
+    #[test]
+    fn gix_tree_names() {
+        let (_dir, repo) = crate::base::tests::gix_repository();
+        reject_invalid_names(&repo);
+    }
+
+    #[cfg(feature = "gix")]
+    #[test]
+    fn gix_objects() {
+        use signature::Signature;
+        use tree::{Builder, Entry, EntryKind};
+
+        let (_dir, repo) = crate::base::tests::gix_repository();
+        let log = Database::write_blob(&repo, b"log").expect("Could not write blob");
+        let mut builder = repo
+            .empty_tree_builder()
+            .expect("Could not create tree builder");
+        builder
+            .insert_blob("build.log", log)
+            .expect("Could not insert");
+        let logs = builder.write().expect("Could not write tree");
+
+        let initial = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .with_attachment("patch.diff", b"old")
+            .and_then(|b| b.with_attachment("patch.diff", b"new"))
+            .expect("Could not add attachment")
+            .build("Test message")
+            .expect("Cannot create commit");
+        assert_eq!(initial.message(), Ok("Test message"));
+        assert_eq!(initial.author().name(), Ok("Foo Bar"));
+        assert_eq!(initial.committer().email(), Ok("foo@example.com"));
+
+        let mut builder = repo
+            .tree_builder(&Database::find_tree(&repo, initial.tree_id()).expect("No tree"))
+            .expect("Could not create tree builder");
+        builder.insert_tree("logs", logs).expect("Could not insert");
+        let tree = builder.write().expect("Could not write tree");
+        let author = repo
+            .signature("Baz", "baz@example.com", 1700000000, -120)
+            .expect("Could not create signature");
+        let message = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .with_author(author)
+            .with_tree(Database::find_tree(&repo, tree).expect("No tree"))
+            .with_parent(initial.clone())
+            .build("Reply")
+            .expect("Cannot create commit");
+        let message = Database::find_commit(&repo, message.id()).expect("No such commit");
+        assert_eq!(message.author().name(), Ok("Baz"));
+        assert_eq!(message.author().time(), 1700000000);
+        assert_eq!(message.author().offset(), -120);
+        assert_eq!(
+            message.parent_ids().into_iter().collect::<Vec<_>>(),
+            [initial.id()],
+        );
+
+        let attachments = repo.attachments(&message).expect("Could not list");
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(
+            attachments[0],
+            Entry {
+                name: "logs/build.log".to_owned(),
+                id: log,
+                kind: EntryKind::Blob,
+            }
+        );
+        assert_eq!(attachments[1].name, "patch.diff");
+        assert_eq!(
+            Database::read_blob(&repo, attachments[1].id).expect("Could not read blob"),
+            b"new"
+        );
+        assert!(Database::read_blob(&repo, message.id()).is_err());
+    }
+}
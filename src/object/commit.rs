@@ -8,7 +8,7 @@
 
 use super::Database;
 use crate::base::Base;
-use crate::error;
+use crate::error::{self, ResultExt};
 
 /// A git commit
 pub trait Commit {
@@ -16,7 +16,7 @@ pub trait Commit {
     type Oid;
 
     /// Type used for git signatures
-    type Signature<'s>
+    type Signature<'s>: super::signature::Signature
     where
         Self: 's;
 
@@ -75,6 +75,64 @@ impl Commit for git2::Commit<'_> {
     }
 }
 
+/// A decoded commit from a [gix::Repository]
+///
+/// Accessing the individual parts of a [gix::Commit] requires decoding, which
+/// may fail. This type holds a commit which was decoded in advance.
+#[cfg(feature = "gix")]
+#[derive(Clone, Debug)]
+pub struct GixCommit {
+    id: gix::ObjectId,
+    inner: gix::objs::Commit,
+}
+
+#[cfg(feature = "gix")]
+impl TryFrom<gix::Commit<'_>> for GixCommit {
+    type Error = gix::objs::decode::Error;
+
+    fn try_from(commit: gix::Commit<'_>) -> Result<Self, Self::Error> {
+        let inner = commit.decode()?.into();
+        Ok(Self {
+            id: commit.id,
+            inner,
+        })
+    }
+}
+
+#[cfg(feature = "gix")]
+impl Commit for GixCommit {
+    type Oid = gix::ObjectId;
+
+    type Signature<'s>
+        = &'s gix::actor::Signature
+    where
+        Self: 's;
+
+    fn id(&self) -> Self::Oid {
+        self.id
+    }
+
+    fn author(&self) -> Self::Signature<'_> {
+        &self.inner.author
+    }
+
+    fn committer(&self) -> Self::Signature<'_> {
+        &self.inner.committer
+    }
+
+    fn message(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(self.inner.message.as_ref())
+    }
+
+    fn parent_ids(&self) -> impl IntoIterator<Item = Self::Oid> + '_ {
+        self.inner.parents.iter().copied()
+    }
+
+    fn tree_id(&self) -> Self::Oid {
+        self.inner.tree
+    }
+}
+
 /// A builder for commits
 pub struct Builder<'r, 'c, R, F>
 where
@@ -129,6 +187,28 @@ where
         Self { tree, ..self }
     }
 
+    /// Add an attachment to the commit's tree
+    ///
+    /// A blob with the given contents is written to the database and added to
+    /// the tree under the given name, replacing any existing entry of that
+    /// name.
+    pub fn with_attachment(self, name: &str, data: &[u8]) -> error::Result<Self, R::InnerError> {
+        use super::tree::Builder;
+
+        let blob = self.database.write_blob(data)?;
+        let mut builder = self.database.tree_builder(&self.tree)?;
+        builder
+            .insert_blob(name, blob)
+            .map_err(Into::into)
+            .wrap_with_kind(error::Kind::CannotBuildTree)?;
+        let tree = builder
+            .write()
+            .map_err(Into::into)
+            .wrap_with_kind(error::Kind::CannotBuildTree)?;
+        let tree = self.database.find_tree(tree)?;
+        Ok(self.with_tree(tree))
+    }
+
     /// Add parents for the commit
     pub fn with_parents(mut self, parents: impl IntoIterator<Item = R::Commit>) -> Self {
         self.parents.extend(parents);
@@ -142,13 +222,24 @@ where
     }
 
     /// Build a [Commit] with a commit message
-    pub fn build(self, message: &str) -> error::Result<F::Output, R::InnerError> {
+    ///
+    /// The message may be a plain [str], a [String] or a
+    /// [Composer](crate::message::Composer). The latter is rendered via
+    /// [Composer::render](crate::message::Composer::render), i.e. trailers which
+    /// cannot be represented in the message are reported as an error.
+    pub fn build<M>(self, message: M) -> error::Result<F::Output, R::InnerError>
+    where
+        M: TryInto<String>,
+        M::Error: Into<error::Kind<R::InnerError>>,
+    {
+        let text: String = message.try_into().map_err(Into::<error::Kind<_>>::into)?;
+
         let parents: Vec<_> = self.parents.iter().collect();
         self.database
             .commit(
                 &self.author,
                 &self.committer,
-                message,
+                &text,
                 &self.tree,
                 parents.as_ref(),
             )
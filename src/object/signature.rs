@@ -0,0 +1,83 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Signature related facilities
+
+use std::str::Utf8Error;
+
+/// A git signature
+///
+/// A signature identifies the author or committer of a commit and the time at
+/// which the commit was authored or committed.
+pub trait Signature {
+    /// Retrieve the name of the signatory
+    fn name(&self) -> Result<&str, Utf8Error>;
+
+    /// Retrieve the email address of the signatory
+    fn email(&self) -> Result<&str, Utf8Error>;
+
+    /// Retrieve the time of the signature in seconds since the Unix epoch
+    fn time(&self) -> i64;
+
+    /// Retrieve the timezone offset of the signature in minutes
+    fn offset(&self) -> i32;
+}
+
+impl<S: Signature + ?Sized> Signature for &S {
+    fn name(&self) -> Result<&str, Utf8Error> {
+        S::name(self)
+    }
+
+    fn email(&self) -> Result<&str, Utf8Error> {
+        S::email(self)
+    }
+
+    fn time(&self) -> i64 {
+        S::time(self)
+    }
+
+    fn offset(&self) -> i32 {
+        S::offset(self)
+    }
+}
+
+#[cfg(feature = "git2")]
+impl Signature for git2::Signature<'_> {
+    fn name(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(self.name_bytes())
+    }
+
+    fn email(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(self.email_bytes())
+    }
+
+    fn time(&self) -> i64 {
+        self.when().seconds()
+    }
+
+    fn offset(&self) -> i32 {
+        self.when().offset_minutes()
+    }
+}
+
+#[cfg(feature = "gix")]
+impl Signature for gix::actor::Signature {
+    fn name(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(self.name.as_ref())
+    }
+
+    fn email(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(self.email.as_ref())
+    }
+
+    fn time(&self) -> i64 {
+        self.time.seconds
+    }
+
+    fn offset(&self) -> i32 {
+        self.time.offset / 60
+    }
+}
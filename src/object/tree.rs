@@ -6,6 +6,8 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 //! Tree related facilities
 
+use std::fmt;
+
 /// A builder for trees
 pub trait Builder {
     /// Type used for representing Object IDs
@@ -14,6 +16,18 @@ pub trait Builder {
     /// Error type associated with this entity
     type Error;
 
+    /// Insert a blob under the given name
+    ///
+    /// Any existing entry with the same name will be replaced. Inserting an
+    /// entry with a name rejected by [check_name] fails.
+    fn insert_blob(&mut self, name: &str, blob: Self::Oid) -> Result<(), Self::Error>;
+
+    /// Insert a subtree under the given name
+    ///
+    /// Any existing entry with the same name will be replaced. Inserting an
+    /// entry with a name rejected by [check_name] fails.
+    fn insert_tree(&mut self, name: &str, tree: Self::Oid) -> Result<(), Self::Error>;
+
     /// Write the tree to the object database
     fn write(self) -> Result<Self::Oid, Self::Error>;
 }
@@ -23,7 +37,117 @@ impl Builder for git2::TreeBuilder<'_> {
     type Oid = git2::Oid;
     type Error = git2::Error;
 
+    fn insert_blob(&mut self, name: &str, blob: Self::Oid) -> Result<(), Self::Error> {
+        check_name(name).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+        self.insert(name, blob, git2::FileMode::Blob.into())
+            .map(|_| ())
+    }
+
+    fn insert_tree(&mut self, name: &str, tree: Self::Oid) -> Result<(), Self::Error> {
+        check_name(name).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+        self.insert(name, tree, git2::FileMode::Tree.into())
+            .map(|_| ())
+    }
+
     fn write(self) -> Result<Self::Oid, Self::Error> {
         git2::TreeBuilder::write(&self)
     }
 }
+
+/// A builder for trees in a [gix::Repository]
+#[cfg(feature = "gix")]
+pub struct GixTreeBuilder<'r> {
+    repo: &'r gix::Repository,
+    tree: gix::objs::Tree,
+}
+
+#[cfg(feature = "gix")]
+impl<'r> GixTreeBuilder<'r> {
+    /// Create a new builder, initialized with the given tree
+    pub(crate) fn new(repo: &'r gix::Repository, tree: gix::objs::Tree) -> Self {
+        Self { repo, tree }
+    }
+
+    /// Insert an entry, replacing any existing entry with the same name
+    fn insert(
+        &mut self,
+        name: &str,
+        oid: gix::ObjectId,
+        kind: gix::objs::tree::EntryKind,
+    ) -> Result<(), crate::error::GixError> {
+        check_name(name).map_err(crate::error::GixError::new)?;
+        self.tree.entries.retain(|e| e.filename != name);
+        self.tree.entries.push(gix::objs::tree::Entry {
+            mode: kind.into(),
+            filename: name.into(),
+            oid,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gix")]
+impl Builder for GixTreeBuilder<'_> {
+    type Oid = gix::ObjectId;
+    type Error = crate::error::GixError;
+
+    fn insert_blob(&mut self, name: &str, blob: Self::Oid) -> Result<(), Self::Error> {
+        self.insert(name, blob, gix::objs::tree::EntryKind::Blob)
+    }
+
+    fn insert_tree(&mut self, name: &str, tree: Self::Oid) -> Result<(), Self::Error> {
+        self.insert(name, tree, gix::objs::tree::EntryKind::Tree)
+    }
+
+    fn write(mut self) -> Result<Self::Oid, Self::Error> {
+        self.tree.entries.sort();
+        self.repo
+            .write_object(&self.tree)
+            .map(gix::Id::detach)
+            .map_err(Self::Error::new)
+    }
+}
+
+/// Check whether a name may be used for a tree entry
+///
+/// Names must not be empty, `.` or `..` and must not contain `/` or NUL
+/// characters. All [Builder]s reject names failing this check.
+pub fn check_name(name: &str) -> Result<(), InvalidName> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\0']) {
+        Err(InvalidName(name.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Error indicating a name not suitable for a tree entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidName(pub String);
+
+impl std::error::Error for InvalidName {}
+
+impl fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid tree entry name {:?}", self.0)
+    }
+}
+
+/// Kind of a tree [Entry]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    /// The entry refers to a blob
+    Blob,
+    /// The entry refers to a subtree
+    Tree,
+}
+
+/// An entry of a tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry<O> {
+    /// Name or path of the entry
+    pub name: String,
+    /// Id of the object the entry refers to
+    pub id: O,
+    /// Kind of the object the entry refers to
+    pub kind: EntryKind,
+}
@@ -17,10 +17,10 @@ use crate::remote;
 pub trait Store<'r>: Base {
     /// Type used for representing references
     type Reference: Reference<
-        Oid = Self::Oid,
-        Name: ToOwned<Owned = <<Self as Base>::InnerError as InnerError>::RefName>,
-        Error: Into<error::Inner<Self::InnerError>>,
-    >;
+            Oid = Self::Oid,
+            Name: ToOwned<Owned = <<Self as Base>::InnerError as InnerError>::RefName>,
+            Error: Into<error::Inner<Self::InnerError>>,
+        >;
 
     /// Type for a basic [Iterator] of [Reference]s
     type References: IntoIterator<Item = Result<Self::Reference, Self::InnerError>>;
@@ -46,6 +46,25 @@ pub trait Store<'r>: Base {
         reflog_msg: &str,
     ) -> error::Result<Self::Reference, Self::InnerError>;
 
+    /// Update or create a [Reference] if it has an expected target
+    ///
+    /// The [Reference] is only updated if its current target is `expected`. If
+    /// `expected` is `None`, the [Reference] will only be created if it does
+    /// not exist yet. In any other case, this fn returns an error and leaves
+    /// the [Reference] untouched.
+    fn set_reference_matching(
+        &'r self,
+        name: &Path,
+        target: Self::Oid,
+        expected: Option<Self::Oid>,
+        reflog_msg: &str,
+    ) -> error::Result<Self::Reference, Self::InnerError>;
+
+    /// Delete a [Reference]
+    ///
+    /// Deleting a reference which does not exist results in an error.
+    fn delete_reference(&'r self, name: &Path) -> error::Result<(), Self::InnerError>;
+
     /// Retrieve all git remote references' names
     fn remote_names(&self) -> error::Result<Self::RemoteNames, Self::InnerError>;
 
@@ -95,11 +114,122 @@ impl<'r> Store<'r> for git2::Repository {
             .wrap_with(|| error::Kind::CannotSetReference(path.to_owned()))
     }
 
+    fn set_reference_matching(
+        &'r self,
+        name: &Path,
+        target: Self::Oid,
+        expected: Option<Self::Oid>,
+        reflog_msg: &str,
+    ) -> error::Result<Self::Reference, Self::InnerError> {
+        let path = name.to_str().ok_or(error::Kind::ReferenceNameError)?;
+        match expected {
+            Some(expected) => self.reference_matching(path, target, true, expected, reflog_msg),
+            None => self.reference(path, target, false, reflog_msg),
+        }
+        .wrap_with(|| error::Kind::CannotSetReference(path.to_owned()))
+    }
+
+    fn delete_reference(&'r self, name: &Path) -> error::Result<(), Self::InnerError> {
+        let path = name.to_str().ok_or(error::Kind::ReferenceNameError)?;
+        self.find_reference(path)
+            .and_then(|mut r| r.delete())
+            .wrap_with(|| error::Kind::CannotDeleteReference(path.to_owned()))
+    }
+
     fn remote_names(&self) -> error::Result<Self::RemoteNames, Self::InnerError> {
         self.remotes().wrap_with_kind(error::Kind::CannotGetRemotes)
     }
 }
 
+#[cfg(feature = "gix")]
+impl<'r> Store<'r> for gix::Repository {
+    type Reference = gix::Reference<'r>;
+    type References = Vec<Result<Self::Reference, Self::InnerError>>;
+    type RemoteNames = Vec<String>;
+
+    fn get_reference(
+        &'r self,
+        path: &Path,
+    ) -> error::Result<Option<Self::Reference>, Self::InnerError> {
+        let name = path.to_str().ok_or(error::Kind::CannotGetReference)?;
+        self.try_find_reference(name)
+            .map_err(error::GixError::new)
+            .wrap_with_kind(error::Kind::CannotGetReference)
+    }
+
+    fn references(&'r self, prefix: &Path) -> error::Result<Self::References, Self::InnerError> {
+        let prefix = format!("{}/", prefix.display());
+        let make_err = || error::Kind::CannotGetReferences(prefix.clone());
+        let platform = gix::Repository::references(self)
+            .map_err(error::GixError::new)
+            .wrap_with(make_err)?;
+        let refs = platform
+            .prefixed(prefix.as_str())
+            .map_err(error::GixError::new)
+            .wrap_with(make_err)?
+            .map(|r| r.map_err(Into::into))
+            .collect();
+        Ok(refs)
+    }
+
+    fn set_reference(
+        &'r self,
+        name: &Path,
+        target: Self::Oid,
+        overwrite: bool,
+        reflog_msg: &str,
+    ) -> error::Result<Self::Reference, Self::InnerError> {
+        use gix::refs::transaction::PreviousValue;
+
+        let path = name.to_str().ok_or(error::Kind::ReferenceNameError)?;
+        let constraint = if overwrite {
+            PreviousValue::Any
+        } else {
+            PreviousValue::MustNotExist
+        };
+        self.reference(path, target, constraint, reflog_msg)
+            .map_err(error::GixError::new)
+            .wrap_with(|| error::Kind::CannotSetReference(path.to_owned()))
+    }
+
+    fn set_reference_matching(
+        &'r self,
+        name: &Path,
+        target: Self::Oid,
+        expected: Option<Self::Oid>,
+        reflog_msg: &str,
+    ) -> error::Result<Self::Reference, Self::InnerError> {
+        use gix::refs::transaction::PreviousValue;
+
+        let path = name.to_str().ok_or(error::Kind::ReferenceNameError)?;
+        let constraint = match expected {
+            Some(id) => PreviousValue::MustExistAndMatch(gix::refs::Target::Object(id)),
+            None => PreviousValue::MustNotExist,
+        };
+        self.reference(path, target, constraint, reflog_msg)
+            .map_err(error::GixError::new)
+            .wrap_with(|| error::Kind::CannotSetReference(path.to_owned()))
+    }
+
+    fn delete_reference(&'r self, name: &Path) -> error::Result<(), Self::InnerError> {
+        let path = name.to_str().ok_or(error::Kind::ReferenceNameError)?;
+        let make_err = || error::Kind::CannotDeleteReference(path.to_owned());
+        self.find_reference(path)
+            .map_err(error::GixError::new)
+            .wrap_with(make_err)?
+            .delete()
+            .map_err(error::GixError::new)
+            .wrap_with(make_err)
+    }
+
+    fn remote_names(&self) -> error::Result<Self::RemoteNames, Self::InnerError> {
+        Ok(gix::Repository::remote_names(self)
+            .into_iter()
+            .map(|n| n.to_string())
+            .collect())
+    }
+}
+
 /// Extension trait for [Iterator]s over [Reference]s
 pub trait References {
     /// [Reference] yielded by this [Iterator]
@@ -214,6 +344,25 @@ impl Reference for git2::Reference<'_> {
     }
 }
 
+#[cfg(feature = "gix")]
+impl Reference for gix::Reference<'_> {
+    type Name = str;
+    type Oid = gix::ObjectId;
+    type Error = std::str::Utf8Error;
+
+    fn name(&self) -> Result<&Self::Name, Self::Error> {
+        std::str::from_utf8(gix::Reference::name(self).as_bstr())
+    }
+
+    fn as_path(&self) -> Result<&Path, Self::Error> {
+        Reference::name(self).map(Path::new)
+    }
+
+    fn target(&self) -> Option<Self::Oid> {
+        gix::Reference::target(self).try_id().map(ToOwned::to_owned)
+    }
+}
+
 /// Parts of a [Reference] associated to an issue
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Parts<'p, O> {
@@ -241,193 +390,14 @@ pub(crate) const HEAD_COMPONENT: &str = "head";
 pub(crate) const LEAF_COMPONENT: &str = "leaves";
 
 #[cfg(test)]
-pub(crate) mod tests {
+mod tests {
     use super::*;
 
-    use std::collections::BTreeSet;
-    use std::path::PathBuf;
-
-    use crate::base::tests::TestOid;
-    use crate::error::tests::TestError;
-
-    impl<'r, T> Store<'r> for (TestStore, T)
-    where
-        T: Base<Oid = <TestStore as Base>::Oid, InnerError = <TestStore as Base>::InnerError>,
-    {
-        type Reference = <TestStore as Store<'r>>::Reference;
-        type References = <TestStore as Store<'r>>::References;
-        type RemoteNames = <TestStore as Store<'r>>::RemoteNames;
-
-        fn get_reference(
-            &'r self,
-            path: &Path,
-        ) -> error::Result<Option<Self::Reference>, Self::InnerError> {
-            self.0.get_reference(path)
-        }
-
-        fn references(
-            &'r self,
-            prefix: &Path,
-        ) -> error::Result<Self::References, Self::InnerError> {
-            self.0.references(prefix)
-        }
-
-        fn set_reference(
-            &'r self,
-            name: &Path,
-            target: Self::Oid,
-            overwrite: bool,
-            reflog_msg: &str,
-        ) -> error::Result<Self::Reference, Self::InnerError> {
-            self.0.set_reference(name, target, overwrite, reflog_msg)
-        }
-
-        fn remote_names(&self) -> error::Result<Self::RemoteNames, Self::InnerError> {
-            self.0.remote_names()
-        }
-    }
-
-    #[derive(Default)]
-    pub struct TestStore {
-        refs: std::sync::Mutex<BTreeSet<TestRef>>,
-        remotes: Vec<String>,
-    }
-
-    impl<'r> Store<'r> for TestStore {
-        type Reference = TestRef;
-        type References = Vec<Result<TestRef, TestError>>;
-        type RemoteNames = Vec<String>;
-
-        fn get_reference(
-            &'r self,
-            path: &Path,
-        ) -> error::Result<Option<Self::Reference>, Self::InnerError> {
-            Ok(self
-                .refs
-                .lock()
-                .expect("Could not access refs")
-                .get(path)
-                .cloned())
-        }
-
-        fn references(
-            &'r self,
-            prefix: &Path,
-        ) -> error::Result<Self::References, Self::InnerError> {
-            let res = self
-                .refs
-                .lock()
-                .expect("Could not access refs")
-                .iter()
-                .filter(|r| r.name.starts_with(prefix))
-                .cloned()
-                .map(Ok)
-                .collect();
-            Ok(res)
-        }
-
-        fn set_reference(
-            &'r self,
-            name: &Path,
-            target: Self::Oid,
-            overwrite: bool,
-            _reflog_msg: &str,
-        ) -> error::Result<Self::Reference, Self::InnerError> {
-            let new = TestRef::from(name.to_owned()).with_target(target);
-            let mut refs = self.refs.lock().expect("Could not access refs");
-            if overwrite {
-                refs.replace(new.clone());
-            } else {
-                refs.insert(new.clone());
-            }
-
-            Ok(new)
-        }
-
-        fn remote_names(&self) -> error::Result<Self::RemoteNames, Self::InnerError> {
-            Ok(self.remotes.clone())
-        }
-    }
-
-    impl Base for TestStore {
-        type Oid = TestOid;
-        type InnerError = TestError;
-    }
-
-    #[derive(Clone, Debug)]
-    pub struct TestRef {
-        name: PathBuf,
-        target: Option<TestOid>,
-    }
-
-    impl TestRef {
-        pub fn with_target(self, target: TestOid) -> Self {
-            Self {
-                target: Some(target),
-                ..self
-            }
-        }
-    }
-
-    impl From<PathBuf> for TestRef {
-        fn from(name: PathBuf) -> Self {
-            Self { name, target: None }
-        }
-    }
-
-    impl From<&str> for TestRef {
-        fn from(path: &str) -> Self {
-            PathBuf::from(path).into()
-        }
-    }
-
-    impl std::borrow::Borrow<Path> for TestRef {
-        fn borrow(&self) -> &Path {
-            &self.name
-        }
-    }
-
-    impl Eq for TestRef {}
-
-    impl PartialEq for TestRef {
-        fn eq(&self, other: &Self) -> bool {
-            PartialEq::eq(&self.name, &other.name)
-        }
-    }
-
-    impl Ord for TestRef {
-        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            Ord::cmp(&self.name, &other.name)
-        }
-    }
-
-    impl PartialOrd for TestRef {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            PartialOrd::partial_cmp(&self.name, &other.name)
-        }
-    }
-
-    impl Reference for TestRef {
-        type Name = str;
-        type Oid = TestOid;
-        type Error = TestError;
-
-        fn name(&self) -> Result<&Self::Name, Self::Error> {
-            self.name.to_str().ok_or(TestError)
-        }
-
-        fn as_path(&self) -> Result<&Path, Self::Error> {
-            Ok(self.name.as_ref())
-        }
-
-        fn target(&self) -> Option<Self::Oid> {
-            self.target
-        }
-    }
+    use crate::memory::Ref;
 
     #[test]
     fn ref_parts_headref() {
-        let reference = TestRef::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/head");
+        let reference = Ref::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/head");
         let parts = reference.parts().expect("Could not extract parts");
         assert_eq!(parts.prefix, Path::new("refs/dit"));
         assert_eq!(parts.issue, "65b56706fdc3501749d008750c61a1f24b888f72");
@@ -436,7 +406,9 @@ pub(crate) mod tests {
 
     #[test]
     fn ref_parts_leaf() {
-        let reference = TestRef::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/leaves/f6bd121bdc2ba5906e412da19191a2eaf2025755");
+        let reference = Ref::from(
+            "refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/leaves/f6bd121bdc2ba5906e412da19191a2eaf2025755",
+        );
         let parts = reference.parts().expect("Could not extract parts");
         assert_eq!(parts.prefix, Path::new("refs/dit"));
         assert_eq!(parts.issue, "65b56706fdc3501749d008750c61a1f24b888f72");
@@ -453,25 +425,25 @@ pub(crate) mod tests {
     #[test]
     fn ref_parts_invalid_head_1() {
         assert_eq!(
-            TestRef::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/head/foo").parts(),
+            Ref::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/head/foo").parts(),
             None,
         );
     }
 
     #[test]
     fn ref_parts_invalid_head_2() {
-        assert_eq!(TestRef::from("refs/dit/foo/head").parts(), None);
+        assert_eq!(Ref::from("refs/dit/foo/head").parts(), None);
     }
 
     #[test]
     fn ref_parts_invalid_leaf_1() {
-        assert_eq!(TestRef::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/foo/f6bd121bdc2ba5906e412da19191a2eaf2025755").parts(), None);
+        assert_eq!(Ref::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/foo/f6bd121bdc2ba5906e412da19191a2eaf2025755").parts(), None);
     }
 
     #[test]
     fn ref_parts_invalid_leaf_2() {
         assert_eq!(
-            TestRef::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/leaves/foo").parts(),
+            Ref::from("refs/dit/65b56706fdc3501749d008750c61a1f24b888f72/leaves/foo").parts(),
             None,
         );
     }
@@ -479,8 +451,76 @@ pub(crate) mod tests {
     #[test]
     fn ref_parts_invalid_leaf_3() {
         assert_eq!(
-            TestRef::from("refs/dit/foo/leaves/f6bd121bdc2ba5906e412da19191a2eaf2025755").parts(),
+            Ref::from("refs/dit/foo/leaves/f6bd121bdc2ba5906e412da19191a2eaf2025755").parts(),
             None,
         );
     }
+
+    #[cfg(feature = "gix")]
+    #[test]
+    fn gix_store() {
+        use crate::object::Database;
+        use crate::object::commit::Commit;
+
+        let (_dir, repo) = crate::base::tests::gix_repository();
+        let id = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .build("Test message")
+            .expect("Cannot create commit")
+            .id();
+        let other = repo
+            .commit_builder(Database::find_commit)
+            .expect("Cannot create commit builder")
+            .build("Other message")
+            .expect("Cannot create commit")
+            .id();
+        let head = format!("refs/dit/{id}/head");
+        let leaf = format!("refs/dit/{id}/leaves/{other}");
+
+        assert!(
+            Store::get_reference(&repo, head.as_ref())
+                .expect("Could not get reference")
+                .is_none()
+        );
+        Store::set_reference(&repo, head.as_ref(), id, false, "")
+            .expect("Could not create reference");
+        assert!(Store::set_reference(&repo, head.as_ref(), other, false, "").is_err());
+        Store::set_reference(&repo, leaf.as_ref(), other, false, "")
+            .expect("Could not create reference");
+        assert!(
+            repo.set_reference_matching(head.as_ref(), other, Some(other), "")
+                .is_err()
+        );
+        repo.set_reference_matching(head.as_ref(), other, Some(id), "")
+            .expect("Could not update reference");
+
+        let reference = Store::get_reference(&repo, head.as_ref())
+            .expect("Could not get reference")
+            .expect("No reference");
+        assert_eq!(Reference::target(&reference), Some(other));
+        assert_eq!(reference.parts().map(|p| p.kind), Some(Kind::Head));
+
+        let mut refs: Vec<_> = Store::references(&repo, "refs/dit".as_ref())
+            .expect("Could not get references")
+            .into_iter()
+            .map(|r| r.expect("Could not get reference").parts().map(|p| p.kind))
+            .collect();
+        refs.sort_by_key(|k| k.is_some_and(|k| k != Kind::Head));
+        assert_eq!(refs, [Some(Kind::Head), Some(Kind::Leaf(other))]);
+
+        Store::delete_reference(&repo, leaf.as_ref()).expect("Could not delete reference");
+        assert!(Store::delete_reference(&repo, leaf.as_ref()).is_err());
+        assert_eq!(
+            Store::references(&repo, "refs/dit".as_ref())
+                .expect("Could not get references")
+                .len(),
+            1,
+        );
+        assert!(
+            Store::remote_names(&repo)
+                .expect("Could not get remotes")
+                .is_empty()
+        );
+    }
 }
@@ -102,6 +102,21 @@ impl RemoteExt for git2::Remote<'_> {
     }
 }
 
+#[cfg(feature = "gix")]
+impl RemoteExt for gix::Remote<'_> {
+    fn issue_refspec(&self, issue: Issue<'_, impl Base>) -> Option<String> {
+        self.name()
+            .and_then(gix::remote::Name::as_symbol)
+            .map(|n| format!("+refs/dit/{0}/*:refs/remotes/{n}/dit/{0}/*", issue.id()))
+    }
+
+    fn all_issues_refspec(&self) -> Option<String> {
+        self.name()
+            .and_then(gix::remote::Name::as_symbol)
+            .map(|name| format!("+refs/dit/*:refs/remotes/{0}/dit/*", name))
+    }
+}
+
 const REMOTES_REF_BASE: &str = "refs/remotes";
 
 #[cfg(test)]
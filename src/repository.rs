@@ -10,16 +10,26 @@
 //! This module provides the `RepositoryExt` extension trait which provides
 //! issue handling utilities for repositories.
 
+use std::collections::HashSet;
+use std::vec;
+
+use crate::cache::{Metadata, MetadataCache};
 use crate::error::{self, ResultExt};
+use crate::index::MessageIndex;
 use crate::issue::{self, Issue};
 use crate::object::{self, commit};
 use crate::reference;
 use crate::remote;
+use crate::trailer::filter::Filter;
+use crate::trailer::spec::ToMap;
 use crate::traversal::Traversible;
 
 /// Set of unique issues
 pub type UniqueIssues<'r, R> = std::collections::HashSet<Issue<'r, R>>;
 
+/// List of issues along with their metadata
+pub type IssuesWithMetadata<'r, R> = Vec<(Issue<'r, R>, Metadata)>;
+
 /// Extension trait for Repositories
 ///
 /// This trait is intended as an extension for repositories. It introduces
@@ -86,6 +96,29 @@ pub trait Repository<'r>: reference::Store<'r> + Sized {
         Err(error::Kind::NoTreeInitFound(message).into())
     }
 
+    /// Find the issue with a given message in it using a [MessageIndex]
+    ///
+    /// Looks up the message in the index provided. If the message is not
+    /// indexed, the index is updated before looking up the message again. If
+    /// the message is still not found, e.g. because it is not reachable from
+    /// any issue reference, this fn falls back to [Self::issue_with_message].
+    fn issue_with_message_indexed(
+        &'r self,
+        index: &mut MessageIndex<Self::Oid>,
+        message: Self::Oid,
+    ) -> error::Result<Issue<'r, Self>, Self::InnerError>
+    where
+        Self: object::Database<'r> + Traversible<'r>,
+    {
+        if index.get(&message).is_none() {
+            index.update(self)?;
+        }
+        match index.get(&message) {
+            Some(issue) => Ok(Issue::new_unchecked(self, issue.clone())),
+            None => self.issue_with_message(message),
+        }
+    }
+
     /// Get issue hashes for a prefix
     ///
     /// This function returns all known issues known to the DIT repo under the
@@ -134,6 +167,97 @@ pub trait Repository<'r>: reference::Store<'r> + Sized {
         Ok(issues)
     }
 
+    /// Get an iterator over all issues
+    ///
+    /// Unlike [Self::issues], this fn does not collect all issues up front.
+    /// Instead, the [IssueIter] returned yields each issue exactly once along
+    /// with its [Origins] as soon as it is found. Issues are yielded in a
+    /// stable order, allowing pagination via [Iterator::skip] and
+    /// [Iterator::take].
+    fn iter_issues(&'r self) -> error::Result<IssueIter<'r, Self>, Self::InnerError> {
+        use remote::{Name, Names};
+
+        let remotes = self
+            .remote_names()?
+            .names()
+            .map(|n| {
+                n.as_str()
+                    .map(ToOwned::to_owned)
+                    .wrap_with_kind(error::Kind::ReferenceNameError)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let prefixes = std::iter::once("refs".to_owned())
+            .chain(self.remote_ref_paths()?)
+            .collect::<Vec<_>>()
+            .into_iter();
+        Ok(IssueIter {
+            repo: self,
+            remotes,
+            prefixes,
+            pending: Default::default(),
+            local: true,
+            seen: Default::default(),
+        })
+    }
+
+    /// Get all issues matching a filter
+    ///
+    /// The metadata of each issue is accumulated starting from the issue's
+    /// local head or, if there is none, the head of the first remote carrying
+    /// the issue. Filters may be constructed conveniently from textual queries
+    /// via [query](crate::trailer::query).
+    fn matching_issues(
+        &'r self,
+        filter: &Filter<'_>,
+    ) -> error::Result<UniqueIssues<'r, Self>, Self::InnerError>
+    where
+        Self: object::Database<'r> + Traversible<'r>,
+    {
+        use reference::Reference;
+
+        let mut res = UniqueIssues::default();
+        for issue in self.issues()? {
+            let head = match issue.local_head()? {
+                Some(head) => Some(head),
+                None => issue.all_remote_heads()?.next().transpose()?,
+            };
+            let Some(head) = head.and_then(|h| h.target()) else {
+                continue;
+            };
+
+            if filter.matches(&issue.metadata_from(head, filter.specs())?) {
+                res.insert(issue);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Get all issues along with their metadata
+    ///
+    /// The metadata of each issue is retrieved via [MetadataCache::metadata],
+    /// i.e. it is only accumulated if the cache does not hold up to date
+    /// metadata for the issue. Entries for issues which no longer exist are
+    /// removed from the cache. Issues without any head are omitted.
+    fn issues_with_metadata<S>(
+        &'r self,
+        cache: &mut MetadataCache<Self::Oid>,
+        specs: S,
+    ) -> error::Result<IssuesWithMetadata<'r, Self>, Self::InnerError>
+    where
+        Self: object::Database<'r> + Traversible<'r>,
+        S: ToMap<Output = Metadata> + Clone,
+    {
+        let mut res = IssuesWithMetadata::default();
+        for issue in self.issues()? {
+            if let Some(metadata) = cache.metadata(&issue, specs.clone())? {
+                res.push((issue, metadata));
+            }
+        }
+        let ids: std::collections::HashSet<_> = res.iter().map(|(i, _)| i.id()).collect();
+        cache.retain(|id| ids.contains(id));
+        Ok(res)
+    }
+
     /// Create a builder for issues
     fn issue_builder<'c>(
         &'r self,
@@ -153,19 +277,96 @@ pub trait Repository<'r>: reference::Store<'r> + Sized {
     }
 }
 
+/// Origins of an issue
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Origins {
+    /// Whether the issue is present in the local repository
+    pub local: bool,
+    /// Names of the remotes carrying the issue
+    pub remotes: Vec<String>,
+}
+
+/// Iterator over issues and their origins
+///
+/// The iterator first yields all local issues, then all issues only carried by
+/// remotes, in the order in which the remotes are reported. Issues from the same
+/// origin are yielded in the order of their ids.
+///
+/// Use [Repository::iter_issues] for creating an instance.
+pub struct IssueIter<'r, R: Repository<'r>> {
+    repo: &'r R,
+    remotes: Vec<String>,
+    prefixes: vec::IntoIter<String>,
+    pending: vec::IntoIter<R::Oid>,
+    local: bool,
+    seen: HashSet<R::Oid>,
+}
+
+impl<'r, R: Repository<'r>> IssueIter<'r, R> {
+    /// Retrieve the ids of all issues with heads under a prefix, in order
+    fn ids_with_prefix(&self, prefix: &str) -> error::Result<Vec<R::Oid>, R::InnerError> {
+        let mut ids = self
+            .repo
+            .issues_with_prefix(prefix)?
+            .into_iter()
+            .map(|i| i.map(|i| i.id().clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        ids.sort_unstable();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    /// Determine the origins of an issue
+    fn origins(&self, issue: &Issue<'r, R>) -> error::Result<Origins, R::InnerError> {
+        let mut remotes = Vec::new();
+        for name in &self.remotes {
+            if issue.remote_head(&name.as_bytes())?.is_some() {
+                remotes.push(name.clone());
+            }
+        }
+        Ok(Origins {
+            local: self.local,
+            remotes,
+        })
+    }
+}
+
+impl<'r, R: Repository<'r>> Iterator for IssueIter<'r, R> {
+    type Item = error::Result<(Issue<'r, R>, Origins), R::InnerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(id) = self.pending.next() {
+                if !self.seen.insert(id.clone()) {
+                    continue;
+                }
+                let issue = Issue::new_unchecked(self.repo, id);
+                return Some(self.origins(&issue).map(|o| (issue, o)));
+            }
+
+            let prefix = self.prefixes.next()?;
+            self.local = prefix == "refs";
+            match self.ids_with_prefix(&prefix) {
+                Ok(ids) => self.pending = ids.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
 #[cfg(feature = "git2")]
 impl Repository<'_> for git2::Repository {}
 
+#[cfg(feature = "gix")]
+impl Repository<'_> for gix::Repository {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::object::tests::TestOdb;
-    use crate::reference::tests::TestStore;
+    use crate::memory;
 
-    type TestRepo = (TestStore, TestOdb);
-
-    impl Repository<'_> for TestRepo {}
+    type TestRepo = memory::Repository;
 
     #[test]
     fn find_issue() {
@@ -177,7 +378,7 @@ mod tests {
             .build("Test message 1")
             .expect("Could not create issue");
 
-        repo.find_issue(issue.id().clone())
+        repo.find_issue(*issue.id())
             .expect("Could not tretrieve issue by id");
     }
 
@@ -226,6 +427,41 @@ mod tests {
         assert_eq!(retrieved_issue.id(), issue.id());
     }
 
+    #[test]
+    fn issue_with_message_indexed() {
+        use object::Database;
+        use object::commit::Commit;
+
+        let repo = TestRepo::default();
+
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test message 1")
+            .expect("Could not create issue");
+        let mut index = MessageIndex::new();
+        let retrieved_issue = repo
+            .issue_with_message_indexed(&mut index, *issue.id())
+            .expect("Could not retrieve issue");
+        assert_eq!(retrieved_issue.id(), issue.id());
+
+        let initial_message = issue
+            .initial_message()
+            .expect("Could not retrieve initial message");
+        let message = repo
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .with_parent(initial_message)
+            .build("Test message 2")
+            .expect("Cannot create commit")
+            .id();
+        let retrieved_issue = repo
+            .issue_with_message_indexed(&mut index, message)
+            .expect("Could not retrieve issue");
+        assert_eq!(retrieved_issue.id(), issue.id());
+        assert_eq!(index.get(&message), None);
+    }
+
     #[test]
     fn issues() {
         let repo = TestRepo::default();
@@ -281,4 +517,211 @@ mod tests {
 
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn matching_issues() {
+        use crate::message::Composer;
+        use crate::trailer::Trailer;
+        use object::Database;
+        use object::commit::Commit;
+        use reference::Store;
+
+        let repo: TestRepo = (
+            memory::Store::default().with_remotes(["origin".to_owned()]),
+            Default::default(),
+        );
+
+        let bug = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build(Composer::new("Bug").with_trailer(Trailer::new("Dit-type", "bug")))
+            .expect("Could not create issue");
+        let initial_message = bug
+            .initial_message()
+            .expect("Could not retrieve initial message");
+        let closing = bug
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(initial_message)
+            .build(Composer::new("Closing").with_trailer(Trailer::new("Dit-status", "closed")))
+            .expect("Could not add message");
+        bug.update_head(closing, true)
+            .expect("Could not update head");
+
+        let feature = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build(Composer::new("Feature").with_trailer(Trailer::new("Dit-type", "feature")))
+            .expect("Could not create issue");
+
+        let remote_bug = repo
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .build(Composer::new("Remote bug").with_trailer(Trailer::new("Dit-type", "bug")))
+            .expect("Cannot create commit")
+            .id();
+        repo.set_reference(
+            format!("refs/remotes/origin/dit/{remote_bug}/head").as_ref(),
+            remote_bug,
+            false,
+            "",
+        )
+        .expect("Could not create remote head");
+
+        let ids = |query: &str| {
+            let filter = query.parse().expect("Could not parse query");
+            let mut ids: Vec<_> = repo
+                .matching_issues(&filter)
+                .expect("Could not filter issues")
+                .into_iter()
+                .map(|i| *i.id())
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(ids("type=bug"), [*bug.id(), remote_bug]);
+        assert_eq!(ids("type=bug -status=closed"), [remote_bug]);
+        assert_eq!(ids("type=feature or status"), [*bug.id(), *feature.id()]);
+        assert_eq!(ids(""), [*bug.id(), *feature.id(), remote_bug]);
+    }
+
+    #[test]
+    fn issues_with_metadata() {
+        use crate::trailer::TrailerValue;
+        use crate::trailer::spec::ISSUE_STATUS_SPEC;
+
+        let repo = TestRepo::default();
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test message 1\n\nDit-status: open")
+            .expect("Could not create issue");
+
+        let mut cache = MetadataCache::new();
+        let issues = repo
+            .issues_with_metadata(&mut cache, &[ISSUE_STATUS_SPEC])
+            .expect("Could not retrieve issues");
+        let status: Vec<_> = issues
+            .iter()
+            .find(|(i, _)| *i == issue)
+            .and_then(|(_, m)| m.get("Dit-status"))
+            .cloned()
+            .expect("No metadata")
+            .into_iter()
+            .collect();
+        assert_eq!(status, [TrailerValue::from_slice("open")]);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn iter_issues() {
+        use object::Database;
+        use object::commit::Commit;
+        use reference::Store;
+
+        let repo: TestRepo = (
+            memory::Store::default().with_remotes(["a".to_owned(), "b".to_owned()]),
+            Default::default(),
+        );
+        let issue = |local: bool, remotes: &[&str]| {
+            let id = repo
+                .commit_builder(TestRepo::find_commit)
+                .expect("Cannot create commit builder")
+                .build("Test message")
+                .expect("Cannot create commit")
+                .id();
+            let issue = Issue::new_unchecked(&repo, id);
+            if local {
+                issue.update_head(id, false).expect("Could not set head");
+            }
+            for remote in remotes {
+                let path = format!("refs/remotes/{remote}/dit/{id}/head");
+                repo.0
+                    .set_reference(path.as_ref(), id, false, "")
+                    .expect("Could not set remote head");
+            }
+            (
+                id,
+                Origins {
+                    local,
+                    remotes: remotes.iter().map(|r| r.to_string()).collect(),
+                },
+            )
+        };
+
+        let remote_only = issue(false, &["b", "a"]);
+        let local = issue(true, &[]);
+        let both = issue(true, &["b"]);
+        let later = issue(false, &["b"]);
+
+        let issues: Vec<_> = repo
+            .iter_issues()
+            .expect("Could not create iterator")
+            .map(|r| r.map(|(i, o)| (*i.id(), o)))
+            .collect::<Result<_, _>>()
+            .expect("Could not retrieve issues");
+        let remote_only = (
+            remote_only.0,
+            Origins {
+                remotes: vec!["a".to_owned(), "b".to_owned()],
+                ..remote_only.1
+            },
+        );
+        assert_eq!(issues, [local, both, remote_only, later]);
+
+        let page: Vec<_> = repo
+            .iter_issues()
+            .expect("Could not create iterator")
+            .skip(1)
+            .take(2)
+            .map(|r| r.map(|(i, _)| *i.id()))
+            .collect::<Result<_, _>>()
+            .expect("Could not retrieve issues");
+        assert_eq!(page, [issues[1].0, issues[2].0]);
+    }
+
+    #[cfg(feature = "gix")]
+    #[test]
+    fn gix_issues() {
+        let (_dir, repo) = crate::base::tests::gix_repository();
+
+        let issue = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .build("Test message 1")
+            .expect("Could not create issue");
+        let message = issue
+            .message_builder()
+            .expect("Could not create builder")
+            .with_parent(issue.initial_message().expect("No initial message"))
+            .build("Test message 2")
+            .expect("Could not add message");
+
+        let ids: Vec<_> = repo
+            .issues()
+            .expect("Could not retrieve issues")
+            .iter()
+            .map(|i| *i.id())
+            .collect();
+        assert_eq!(ids, [*issue.id()]);
+        repo.find_issue(*issue.id())
+            .expect("Could not retrieve issue by id");
+        assert_eq!(
+            repo.issue_with_message(message)
+                .expect("Could not find issue")
+                .id(),
+            issue.id(),
+        );
+
+        let mut messages: Vec<_> = issue
+            .messages()
+            .expect("Could not get messages")
+            .collect::<Result<_, _>>()
+            .expect("Could not retrieve messages");
+        messages.sort();
+        let mut expected = vec![*issue.id(), message];
+        expected.sort();
+        assert_eq!(messages, expected);
+    }
 }
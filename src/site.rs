@@ -0,0 +1,595 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Static site generation
+//!
+//! This module provides the [Site] type, a static HTML rendering of all issues
+//! of a repository. A site consists of an index listing all issues along with
+//! their status and type, and a page for each issue showing the issue's
+//! messages in their reply structure. References to messages of other issues
+//! are rendered as links, and each issue page links back to the issues
+//! referring to it.
+//!
+//! The rendering is deterministic: rendering the same repository state twice
+//! yields identical sites. Hence, sites may be generated and published by CI
+//! jobs without the need for a server.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::crossref::{Resolver, Target};
+use crate::error::{self, ResultExt};
+use crate::issue::Issue;
+use crate::message::Message;
+use crate::object::Database;
+use crate::object::commit::Commit;
+use crate::object::signature::Signature;
+use crate::reference::Reference;
+use crate::repository::Repository;
+use crate::trailer::Trailer;
+use crate::trailer::accumulation::ValueAccumulator;
+use crate::trailer::spec::{ISSUE_LABEL_SPEC, ISSUE_STATUS_SPEC, ISSUE_TYPE_SPEC};
+use crate::traversal::Traversible;
+use crate::utils::{DateTime, Escaped};
+
+/// Path of the index page
+pub const INDEX_PATH: &str = "index.html";
+
+/// Path of the stylesheet
+pub const STYLE_PATH: &str = "style.css";
+
+/// Directory containing the pages of individual issues
+pub const ISSUES_DIR: &str = "issues";
+
+/// Stylesheet used by all pages
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 60em; margin: auto; padding: 1em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.25em 0.5em; border-bottom: 1px solid #ddd; }
+ul.thread { list-style: none; padding-left: 1.5em; }
+ul.thread > li { border-left: 2px solid #ddd; padding-left: 0.5em; margin: 0.5em 0; }
+article p { white-space: pre-wrap; }
+.meta { color: #666; }
+dl.trailers { display: grid; grid-template-columns: max-content auto; gap: 0 1em; }
+dl.trailers dd { margin: 0; }
+";
+
+/// A static site rendered from a repository
+///
+/// The site maps paths relative to the site's root to the contents of the
+/// files at those locations. Pages of issues are placed in [ISSUES_DIR] and
+/// named after the issue's id.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Site {
+    pages: BTreeMap<PathBuf, String>,
+}
+
+impl Site {
+    /// Render a site for all issues in a repository
+    ///
+    /// Issues are listed from the newest to the oldest one. The metadata shown
+    /// is accumulated from each issue's local head.
+    pub fn render<'r, R>(repo: &'r R) -> error::Result<Self, R::InnerError>
+    where
+        R: Repository<'r> + Database<'r> + Traversible<'r>,
+    {
+        let mut summaries = repo
+            .issues()?
+            .into_iter()
+            .map(Summary::new)
+            .collect::<error::Result<Vec<_>, _>>()?;
+        summaries.sort_by(|a, b| {
+            b.time
+                .cmp(&a.time)
+                .then_with(|| a.issue.id().cmp(b.issue.id()))
+        });
+        let titles: BTreeMap<_, _> = summaries
+            .iter()
+            .map(|s| (s.issue.id().clone(), s.title.clone()))
+            .collect();
+
+        let mut pages = BTreeMap::new();
+        pages.insert(STYLE_PATH.into(), STYLE.to_owned());
+
+        let mut index = String::new();
+        write_index(&mut index, &summaries).wrap_with_kind(error::Kind::CannotWriteSite)?;
+        pages.insert(INDEX_PATH.into(), index);
+
+        let mut resolver = Resolver::new(repo);
+        let threads = summaries
+            .iter()
+            .map(|s| thread_entries(repo, &mut resolver, &titles, s))
+            .collect::<error::Result<Vec<_>, _>>()?;
+
+        // Issues referring to each issue, computed once for all pages
+        let mut referencing: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for (summary, entries) in summaries.iter().zip(&threads) {
+            let id = summary.issue.id();
+            entries
+                .iter()
+                .flat_map(|e| &e.references)
+                .filter_map(|r| match r {
+                    Link::Message { issue, .. } if issue != id => Some(issue),
+                    _ => None,
+                })
+                .for_each(|issue| {
+                    referencing.entry(issue.clone()).or_default().insert(id);
+                });
+        }
+
+        for (summary, entries) in summaries.iter().zip(&threads) {
+            let referencing: Vec<_> = referencing
+                .get(summary.issue.id())
+                .into_iter()
+                .flatten()
+                .map(|i| ((*i).clone(), titles[*i].clone()))
+                .collect();
+            let mut page = String::new();
+            write_issue(&mut page, summary, entries, &referencing)
+                .wrap_with_kind(error::Kind::CannotWriteSite)?;
+            pages.insert(issue_path(summary.issue.id()), page);
+        }
+
+        Ok(Self { pages })
+    }
+
+    /// Retrieve the files of the site, indexed by their paths
+    pub fn pages(&self) -> &BTreeMap<PathBuf, String> {
+        &self.pages
+    }
+
+    /// Extract the files of the site, indexed by their paths
+    pub fn into_pages(self) -> BTreeMap<PathBuf, String> {
+        self.pages
+    }
+
+    /// Write the site to a directory
+    ///
+    /// Missing directories are created. Existing files are overwritten, but
+    /// files not part of the site are left untouched.
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        self.pages.iter().try_for_each(|(path, content)| {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, content)
+        })
+    }
+}
+
+/// Retrieve the path of the page of an issue relative to the site's root
+pub fn issue_path(id: &impl fmt::Display) -> PathBuf {
+    Path::new(ISSUES_DIR).join(format!("{id}.html"))
+}
+
+/// Overview of a single issue
+struct Summary<'r, R: Database<'r>> {
+    issue: Issue<'r, R>,
+    title: String,
+    author: String,
+    time: i64,
+    offset: i32,
+    status: Option<String>,
+    kind: Option<String>,
+    labels: Vec<String>,
+}
+
+impl<'r, R> Summary<'r, R>
+where
+    R: Repository<'r> + Database<'r> + Traversible<'r>,
+{
+    /// Create an overview of an issue
+    fn new(issue: Issue<'r, R>) -> error::Result<Self, R::InnerError> {
+        let initial = issue.initial_message()?;
+        let title = initial
+            .subject()
+            .wrap_with_kind(error::Kind::MalformedMessage)?
+            .to_owned();
+        let author = initial.author();
+        let name = author
+            .name()
+            .wrap_with_kind(error::Kind::CannotGetSignature)?
+            .to_owned();
+
+        // Issues may exist only on a remote, hence we fall back to remote heads
+        let head = issue
+            .all_heads()?
+            .filter_map(|r| r.map(|r| r.target()).transpose())
+            .next()
+            .transpose()?
+            .ok_or_else(|| error::Kind::CannotFindIssueHead(issue.id().clone()))?;
+        let mut metadata: std::collections::HashMap<String, ValueAccumulator> =
+            issue.metadata_from(head, [ISSUE_STATUS_SPEC, ISSUE_TYPE_SPEC, ISSUE_LABEL_SPEC])?;
+        let mut values = |key: &str| -> Vec<String> {
+            metadata
+                .remove(key)
+                .into_iter()
+                .flatten()
+                .map(|v| v.to_string())
+                .collect()
+        };
+        let status = values(&ISSUE_STATUS_SPEC.key).pop();
+        let kind = values(&ISSUE_TYPE_SPEC.key).pop();
+        let labels = values(&ISSUE_LABEL_SPEC.key);
+
+        Ok(Self {
+            title,
+            author: name,
+            time: author.time(),
+            offset: author.offset(),
+            status,
+            kind,
+            labels,
+            issue,
+        })
+    }
+}
+
+/// A message prepared for rendering
+struct Entry<O> {
+    id: O,
+    depth: usize,
+    subject: String,
+    author: String,
+    time: i64,
+    offset: i32,
+    body: Vec<String>,
+    trailers: Vec<Trailer>,
+    references: Vec<Link<O>>,
+}
+
+/// A reference prepared for rendering
+enum Link<O> {
+    /// A message of an issue, along with the issue's id and title
+    Message { issue: O, message: O, title: String },
+    /// A commit which is not part of any issue
+    Code(O),
+    /// A commit which is not present in the repository
+    Unknown(O),
+}
+
+/// Prepare the messages of a single issue for rendering
+fn thread_entries<'r, R>(
+    repo: &'r R,
+    resolver: &mut Resolver<'r, R>,
+    titles: &BTreeMap<R::Oid, String>,
+    summary: &Summary<'r, R>,
+) -> error::Result<Vec<Entry<R::Oid>>, R::InnerError>
+where
+    R: Repository<'r> + Database<'r> + Traversible<'r>,
+{
+    let title = |id: &R::Oid| titles.get(id).cloned().unwrap_or_else(|| id.to_string());
+
+    let mut entries = Vec::new();
+    for node in &summary.issue.thread()? {
+        let message = repo.find_commit(node.id().clone())?;
+        let parts = message
+            .parts()
+            .wrap_with_kind(error::Kind::MalformedMessage)?;
+        let author = message.author();
+        let references = resolver
+            .references(&message)?
+            .into_iter()
+            .map(|target| match target {
+                Target::Message { issue, message } => Link::Message {
+                    title: title(issue.id()),
+                    issue: issue.id().clone(),
+                    message,
+                },
+                Target::Code(id) => Link::Code(id),
+                Target::Unknown(id) => Link::Unknown(id),
+            })
+            .collect();
+        entries.push(Entry {
+            id: node.id().clone(),
+            depth: node.depth(),
+            subject: parts.subject.to_owned(),
+            author: author
+                .name()
+                .wrap_with_kind(error::Kind::CannotGetSignature)?
+                .to_owned(),
+            time: author.time(),
+            offset: author.offset(),
+            body: parts.body.iter().map(|p| p.to_string()).collect(),
+            trailers: parts.trailers,
+            references,
+        });
+    }
+    Ok(entries)
+}
+
+/// Write the index page
+fn write_index<'r, R: Database<'r>>(
+    out: &mut impl Write,
+    issues: &[Summary<'r, R>],
+) -> fmt::Result {
+    write_header(out, "Issues", "")?;
+    writeln!(out, "<h1>Issues</h1>")?;
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>Title</th><th>Status</th><th>Type</th><th>Author</th><th>Date</th></tr>",
+    )?;
+    for summary in issues {
+        writeln!(
+            out,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            Escaped(&issue_path(summary.issue.id()).display()),
+            Escaped(&summary.title),
+            Escaped(&summary.status.as_deref().unwrap_or_default()),
+            Escaped(&summary.kind.as_deref().unwrap_or_default()),
+            Escaped(&summary.author),
+            DateTime::new(summary.time, summary.offset).rfc5322(),
+        )?;
+    }
+    writeln!(out, "</table>")?;
+    write_footer(out)
+}
+
+/// Write the page of an issue
+///
+/// `referencing` holds the ids and titles of all other issues referring to
+/// the issue.
+fn write_issue<'r, R: Database<'r>>(
+    out: &mut impl Write,
+    summary: &Summary<'r, R>,
+    entries: &[Entry<R::Oid>],
+    referencing: &[(R::Oid, String)],
+) -> fmt::Result {
+    write_header(out, &summary.title, "../")?;
+    writeln!(out, "<p><a href=\"../{INDEX_PATH}\">All issues</a></p>")?;
+    writeln!(out, "<h1>{}</h1>", Escaped(&summary.title))?;
+    writeln!(out, "<dl>")?;
+    let metadata = [
+        ("Status", summary.status.as_slice()),
+        ("Type", summary.kind.as_slice()),
+        ("Labels", summary.labels.as_slice()),
+    ];
+    for (name, values) in metadata.iter().filter(|(_, v)| !v.is_empty()) {
+        writeln!(
+            out,
+            "<dt>{name}</dt><dd>{}</dd>",
+            Escaped(&values.join(", "))
+        )?;
+    }
+    writeln!(out, "</dl>")?;
+
+    if !referencing.is_empty() {
+        writeln!(out, "<h2>Referenced by</h2>")?;
+        writeln!(out, "<ul>")?;
+        for (id, title) in referencing {
+            writeln!(out, "<li><a href=\"{id}.html\">{}</a></li>", Escaped(title))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    writeln!(out, "<h2>Messages</h2>")?;
+    // Number of lists currently open, each of which has an open item
+    let mut open = 0;
+    for entry in entries {
+        while open > entry.depth + 1 {
+            writeln!(out, "</li>\n</ul>")?;
+            open -= 1;
+        }
+        if open == entry.depth + 1 {
+            writeln!(out, "</li>")?;
+        }
+        while open < entry.depth + 1 {
+            writeln!(out, "<ul class=\"thread\">")?;
+            open += 1;
+        }
+        write!(out, "<li>")?;
+        write_entry(out, entry)?;
+    }
+    (0..open).try_for_each(|_| writeln!(out, "</li>\n</ul>"))?;
+
+    write_footer(out)
+}
+
+/// Write a single message
+fn write_entry<O: fmt::Display>(out: &mut impl Write, entry: &Entry<O>) -> fmt::Result {
+    writeln!(out, "<article id=\"{}\">", entry.id)?;
+    writeln!(out, "<h3>{}</h3>", Escaped(&entry.subject))?;
+    writeln!(
+        out,
+        "<p class=\"meta\">{} on {}</p>",
+        Escaped(&entry.author),
+        DateTime::new(entry.time, entry.offset).rfc5322(),
+    )?;
+    for paragraph in &entry.body {
+        writeln!(out, "<p>{}</p>", Escaped(paragraph))?;
+    }
+    if !entry.trailers.is_empty() {
+        writeln!(out, "<dl class=\"trailers\">")?;
+        for trailer in &entry.trailers {
+            writeln!(
+                out,
+                "<dt>{}</dt><dd>{}</dd>",
+                Escaped(&trailer.key),
+                Escaped(&trailer.value),
+            )?;
+        }
+        writeln!(out, "</dl>")?;
+    }
+    if !entry.references.is_empty() {
+        writeln!(out, "<ul class=\"references\">")?;
+        for reference in &entry.references {
+            match reference {
+                Link::Message {
+                    issue,
+                    message,
+                    title,
+                } => writeln!(
+                    out,
+                    "<li>Refers to <a href=\"{issue}.html#{message}\">{}</a></li>",
+                    Escaped(title),
+                )?,
+                Link::Code(id) => writeln!(out, "<li>Refers to commit <code>{id}</code></li>")?,
+                Link::Unknown(id) => {
+                    writeln!(out, "<li>Refers to unknown commit <code>{id}</code></li>")?
+                }
+            }
+        }
+        writeln!(out, "</ul>")?;
+    }
+    writeln!(out, "</article>")
+}
+
+/// Write the beginning of a page
+///
+/// The `root` is the relative path from the page to the site's root.
+fn write_header(out: &mut impl Write, title: &str, root: &str) -> fmt::Result {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", Escaped(&title))?;
+    writeln!(out, "<link rel=\"stylesheet\" href=\"{root}{STYLE_PATH}\">")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")
+}
+
+/// Write the end of a page
+fn write_footer(out: &mut impl Write) -> fmt::Result {
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory;
+    use crate::message::Composer;
+
+    type TestRepo = memory::Repository;
+
+    #[test]
+    fn render() {
+        let repo = TestRepo::default();
+        let author = |time| memory::Signature::new("Foo <3", "foo@example.com").with_time(time, 0);
+
+        let first = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .with_author(author(10))
+            .build(
+                Composer::new("Crash & burn")
+                    .with_body("It crashes.")
+                    .with_trailer(Trailer::new("Dit-status", "open"))
+                    .with_trailer(Trailer::new("Dit-type", "bug")),
+            )
+            .expect("Could not create issue");
+        let second = repo
+            .issue_builder()
+            .expect("Could not create issue builder")
+            .with_author(author(20))
+            .build("Feature")
+            .expect("Could not create issue");
+        let reply = second
+            .message_builder()
+            .expect("Could not create builder")
+            .with_author(author(30))
+            .with_parent(second.initial_message().expect("No initial message"))
+            .with_parent(first.initial_message().expect("No initial message"))
+            .build("Re: Feature\n\nSee the crash.")
+            .expect("Could not add message");
+
+        let site = Site::render(&repo).expect("Could not render site");
+        assert_eq!(site, Site::render(&repo).expect("Could not render site"));
+        let first_path = issue_path(first.id());
+        let second_path = issue_path(second.id());
+        assert_eq!(
+            site.pages().keys().collect::<Vec<_>>(),
+            {
+                let mut paths = vec![
+                    PathBuf::from(INDEX_PATH),
+                    PathBuf::from(STYLE_PATH),
+                    first_path.clone(),
+                    second_path.clone(),
+                ];
+                paths.sort();
+                paths
+            }
+            .iter()
+            .collect::<Vec<_>>(),
+        );
+
+        let index = &site.pages()[Path::new(INDEX_PATH)];
+        let first_row = format!(
+            "<tr><td><a href=\"{}\">Crash &amp; burn</a></td><td>open</td><td>bug</td>\
+                <td>Foo &lt;3</td><td>Thu, 1 Jan 1970 00:00:10 +0000</td></tr>",
+            first_path.display(),
+        );
+        let first_pos = index.find(&first_row).expect("First issue not listed");
+        let second_pos = index
+            .find(&format!(
+                "<a href=\"{}\">Feature</a>",
+                second_path.display()
+            ))
+            .expect("Second issue not listed");
+        assert!(second_pos < first_pos);
+
+        let first_page = &site.pages()[&first_path];
+        assert!(first_page.contains("<dt>Dit-status</dt><dd>open</dd>"));
+        assert!(first_page.contains(&format!("<a href=\"{}.html\">Feature</a>", second.id())));
+
+        let second_page = &site.pages()[&second_path];
+        assert!(second_page.contains(&format!("<article id=\"{reply}\">")));
+        assert!(second_page.contains("<p>See the crash.</p>"));
+        assert!(second_page.contains(&format!(
+            "<a href=\"{}.html#{}\">Crash &amp; burn</a>",
+            first.id(),
+            first.id(),
+        )));
+        let thread = &second_page[second_page.find("<ul class").expect("No thread")..];
+        assert!(thread.starts_with(&format!(
+            "<ul class=\"thread\">\n<li><article id=\"{}\">",
+            second.id(),
+        )));
+        assert!(thread.contains(&format!(
+            "</article>\n<ul class=\"thread\">\n<li><article id=\"{reply}\">",
+        )));
+        assert!(thread.ends_with("</article>\n</li>\n</ul>\n</li>\n</ul>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn render_remote_only() {
+        use crate::reference::Store;
+
+        let repo: TestRepo = (
+            memory::Store::default().with_remotes(["origin".to_owned()]),
+            Default::default(),
+        );
+        let issue = repo
+            .commit_builder(TestRepo::find_commit)
+            .expect("Cannot create commit builder")
+            .build(Composer::new("Remote bug").with_trailer(Trailer::new("Dit-status", "open")))
+            .expect("Cannot create commit")
+            .id();
+        repo.0
+            .set_reference(
+                format!("refs/remotes/origin/dit/{issue}/head").as_ref(),
+                issue,
+                false,
+                "",
+            )
+            .expect("Could not set reference");
+
+        let site = Site::render(&repo).expect("Could not render site");
+        let index = &site.pages()[Path::new(INDEX_PATH)];
+        assert!(index.contains(&format!(
+            "<a href=\"{}\">Remote bug</a></td><td>open</td>",
+            issue_path(&issue).display(),
+        )));
+        assert!(site.pages().contains_key(&issue_path(&issue)));
+    }
+}
@@ -17,6 +17,7 @@
 pub mod accumulation;
 pub mod filter;
 pub mod iter;
+pub mod query;
 pub mod spec;
 
 use std::fmt;
@@ -40,6 +41,20 @@ impl From<String> for TrailerKey {
     }
 }
 
+impl FromStr for TrailerKey {
+    type Err = String;
+
+    /// Parse a key, which must be non-empty and consist of alphanumeric
+    /// characters and dashes only
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '-') {
+            Ok(TrailerKey(s.to_owned()))
+        } else {
+            Err(s.to_owned())
+        }
+    }
+}
+
 impl AsRef<String> for TrailerKey {
     fn as_ref(&self) -> &String {
         &self.0
@@ -61,6 +76,11 @@ impl fmt::Display for TrailerKey {
 /// ```
 ///
 #[derive(Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Clone)]
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
 pub enum TrailerValue {
     Int(i64),
     String(String),
@@ -146,10 +166,11 @@ impl FromStr for Trailer {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.split_once([':', '='])
-            .filter(|(k, _)| k.chars().all(|c| c.is_alphanumeric() || c == '-'))
-            .map(|(k, v)| Trailer::new(k, v.trim()))
-            .ok_or_else(|| s.to_owned())
+        let (key, value) = s.split_once([':', '=']).ok_or_else(|| s.to_owned())?;
+        Ok(Trailer {
+            key: key.parse().map_err(|_| s.to_owned())?,
+            value: TrailerValue::from_slice(value.trim()),
+        })
     }
 }
 
@@ -205,4 +226,9 @@ mod tests {
     fn empty_trailer() {
         assert!(Trailer::from_str("").is_err());
     }
+
+    #[test]
+    fn empty_key() {
+        assert!(Trailer::from_str(": foo").is_err());
+    }
 }
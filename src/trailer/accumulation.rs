@@ -22,12 +22,28 @@ use super::{Trailer, TrailerValue};
 /// Policy for accumulating trailers
 ///
 /// These enum values represent accumulation policies for trailers, e.g. how
-/// trailer values are accumulated.
+/// trailer values are accumulated. Values are expected to be processed from the
+/// newest to the oldest one, i.e. in the order in which the messages of an
+/// issue are traversed.
 ///
 #[derive(Clone)]
 pub enum AccumulationPolicy {
+    /// Only keep the newest value
     Latest,
+    /// Keep all values
     List,
+    /// Only keep the oldest value
+    First,
+    /// Keep all distinct values
+    Set,
+    /// Count the values
+    Count,
+    /// Maintain a set of labels
+    ///
+    /// Values of the form `+label` or `label` add a label, `-label` removes
+    /// it. The newest decision for a given label wins. Empty labels are
+    /// ignored.
+    Labels,
 }
 
 /// Accumulation helper for trailer values
@@ -39,6 +55,10 @@ pub enum AccumulationPolicy {
 pub enum ValueAccumulator {
     Latest(Option<TrailerValue>),
     List(Vec<TrailerValue>),
+    First(Option<TrailerValue>),
+    Set(collections::BTreeSet<TrailerValue>),
+    Count(usize),
+    Labels(collections::BTreeMap<String, bool>),
 }
 
 impl ValueAccumulator {
@@ -52,6 +72,23 @@ impl ValueAccumulator {
                 }
             }
             ValueAccumulator::List(values) => values.push(new_value),
+            ValueAccumulator::First(value) => *value = Some(new_value),
+            ValueAccumulator::Set(values) => {
+                values.insert(new_value);
+            }
+            ValueAccumulator::Count(count) => *count += 1,
+            ValueAccumulator::Labels(labels) => {
+                let value = new_value.to_string();
+                let (label, set) = if let Some(label) = value.strip_prefix('-') {
+                    (label, false)
+                } else {
+                    (value.strip_prefix('+').unwrap_or(&value), true)
+                };
+                let label = label.trim();
+                if !label.is_empty() {
+                    labels.entry(label.to_owned()).or_insert(set);
+                }
+            }
         }
     }
 }
@@ -61,6 +98,10 @@ impl From<AccumulationPolicy> for ValueAccumulator {
         match policy {
             AccumulationPolicy::Latest => ValueAccumulator::Latest(None),
             AccumulationPolicy::List => ValueAccumulator::List(Vec::new()),
+            AccumulationPolicy::First => ValueAccumulator::First(None),
+            AccumulationPolicy::Set => ValueAccumulator::Set(Default::default()),
+            AccumulationPolicy::Count => ValueAccumulator::Count(0),
+            AccumulationPolicy::Labels => ValueAccumulator::Labels(Default::default()),
         }
     }
 }
@@ -73,6 +114,17 @@ impl IntoIterator for ValueAccumulator {
         match self {
             ValueAccumulator::Latest(value) => Box::new(value.into_iter()),
             ValueAccumulator::List(values) => Box::new(values.into_iter()),
+            ValueAccumulator::First(value) => Box::new(value.into_iter()),
+            ValueAccumulator::Set(values) => Box::new(values.into_iter()),
+            ValueAccumulator::Count(count) => {
+                Box::new(std::iter::once(TrailerValue::Int(count as i64)))
+            }
+            ValueAccumulator::Labels(labels) => Box::new(
+                labels
+                    .into_iter()
+                    .filter(|(_, set)| *set)
+                    .map(|(label, _)| TrailerValue::from_slice(&label)),
+            ),
         }
     }
 }
@@ -281,6 +333,69 @@ mod tests {
         assert_eq!(values.next(), None);
     }
 
+    #[test]
+    fn accumulate_first() {
+        let mut acc = ValueAccumulator::from(AccumulationPolicy::First);
+        acc.process(TrailerValue::from_slice("foo-bar"));
+        acc.process(TrailerValue::from_slice("baz"));
+
+        let values: Vec<_> = acc.into_iter().collect();
+        assert_eq!(values, [TrailerValue::from_slice("baz")]);
+    }
+
+    #[test]
+    fn accumulate_set() {
+        let mut acc = ValueAccumulator::from(AccumulationPolicy::Set);
+        acc.process(TrailerValue::from_slice("foo-bar"));
+        acc.process(TrailerValue::from_slice("baz"));
+        acc.process(TrailerValue::from_slice("foo-bar"));
+
+        let values: Vec<_> = acc.into_iter().collect();
+        assert_eq!(
+            values,
+            [
+                TrailerValue::from_slice("baz"),
+                TrailerValue::from_slice("foo-bar"),
+            ]
+        );
+    }
+
+    #[test]
+    fn accumulate_count() {
+        let mut acc = ValueAccumulator::from(AccumulationPolicy::Count);
+        let values: Vec<_> = acc.clone().into_iter().collect();
+        assert_eq!(values, [TrailerValue::Int(0)]);
+
+        acc.process(TrailerValue::from_slice("foo-bar"));
+        acc.process(TrailerValue::from_slice("foo-bar"));
+        let values: Vec<_> = acc.into_iter().collect();
+        assert_eq!(values, [TrailerValue::Int(2)]);
+    }
+
+    #[test]
+    fn accumulate_labels() {
+        // Values are processed from the newest to the oldest
+        let mut acc = ValueAccumulator::from(AccumulationPolicy::Labels);
+        acc.process(TrailerValue::from_slice("-bug"));
+        acc.process(TrailerValue::from_slice("+ui"));
+        acc.process(TrailerValue::from_slice("+bug"));
+        acc.process(TrailerValue::from_slice("wontfix"));
+        acc.process(TrailerValue::from_slice("-ui"));
+        acc.process(TrailerValue::from_slice("-5"));
+        acc.process(TrailerValue::from_slice("+5"));
+        acc.process(TrailerValue::from_slice("+"));
+        acc.process(TrailerValue::from_slice("- "));
+
+        let values: Vec<_> = acc.into_iter().collect();
+        assert_eq!(
+            values,
+            [
+                TrailerValue::from_slice("ui"),
+                TrailerValue::from_slice("wontfix"),
+            ]
+        );
+    }
+
     // Accumulator tests
 
     #[test]
@@ -20,16 +20,26 @@
 
 use std::borrow::Borrow;
 
+use super::TrailerValue;
 use super::accumulation::ValueAccumulator;
 use super::spec::TrailerSpec;
-use super::TrailerValue;
 
 /// Type for matching TrailerValues
 ///
+/// Additional matchers may be available depending on enabled features.
+///
+#[non_exhaustive]
 pub enum ValueMatcher {
     Any,
     Equals(TrailerValue),
     Contains(String),
+    /// Matches integer values less than the one given
+    Less(i64),
+    /// Matches integer values greater than the one given
+    Greater(i64),
+    /// Matches values whose string representation matches a regex
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
 }
 
 impl ValueMatcher {
@@ -40,6 +50,10 @@ impl ValueMatcher {
             ValueMatcher::Any => true,
             ValueMatcher::Equals(v) => value == v,
             ValueMatcher::Contains(s) => value.to_string().contains(s),
+            ValueMatcher::Less(n) => matches!(value, TrailerValue::Int(i) if i < n),
+            ValueMatcher::Greater(n) => matches!(value, TrailerValue::Int(i) if i > n),
+            #[cfg(feature = "regex")]
+            ValueMatcher::Regex(r) => r.is_match(&value.to_string()),
         }
     }
 
@@ -65,10 +79,7 @@ impl<'a> TrailerFilter<'a> {
     /// Create a new trailer filter
     ///
     pub fn new(trailer: TrailerSpec<'a>, matcher: ValueMatcher) -> Self {
-        Self {
-            trailer,
-            matcher,
-        }
+        Self { trailer, matcher }
     }
 
     /// Check whether an issue matches the filter
@@ -83,7 +94,7 @@ impl<'a> TrailerFilter<'a> {
         accumulator: &std::collections::HashMap<String, ValueAccumulator>,
     ) -> bool {
         let values = accumulator
-            .get(self.trailer.key)
+            .get(self.trailer.key.as_ref())
             .cloned()
             .unwrap_or_default();
         self.matcher.matches_any(values)
@@ -95,3 +106,130 @@ impl<'a> TrailerFilter<'a> {
         &self.trailer
     }
 }
+
+/// Combination of trailer based filters
+///
+/// This type allows combining [TrailerFilter]s. Like a [TrailerFilter], a
+/// [Filter] is evaluated against a metadata map retrieved via accumulation.
+/// Use [Filter::specs] for retrieving the specifications required for the
+/// accumulation.
+///
+pub enum Filter<'a> {
+    /// Match a single trailer
+    Trailer(TrailerFilter<'a>),
+    /// Match if the inner filter does not match
+    Not(Box<Filter<'a>>),
+    /// Match if all of the inner filters match
+    And(Vec<Filter<'a>>),
+    /// Match if any of the inner filters match
+    Or(Vec<Filter<'a>>),
+}
+
+impl<'a> Filter<'a> {
+    /// Check whether an issue matches the filter
+    ///
+    /// See [TrailerFilter::matches] for details.
+    ///
+    pub fn matches(
+        &self,
+        accumulator: &std::collections::HashMap<String, ValueAccumulator>,
+    ) -> bool {
+        match self {
+            Filter::Trailer(f) => f.matches(accumulator),
+            Filter::Not(f) => !f.matches(accumulator),
+            Filter::And(fs) => fs.iter().all(|f| f.matches(accumulator)),
+            Filter::Or(fs) => fs.iter().any(|f| f.matches(accumulator)),
+        }
+    }
+
+    /// Retrieve the specs of all trailers involved in this filter
+    ///
+    pub fn specs(&self) -> Vec<&TrailerSpec<'a>> {
+        match self {
+            Filter::Trailer(f) => vec![f.spec()],
+            Filter::Not(f) => f.specs(),
+            Filter::And(fs) | Filter::Or(fs) => fs.iter().flat_map(Filter::specs).collect(),
+        }
+    }
+}
+
+impl<'a> From<TrailerFilter<'a>> for Filter<'a> {
+    fn from(filter: TrailerFilter<'a>) -> Self {
+        Filter::Trailer(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::borrow::Cow;
+
+    use crate::trailer::Trailer;
+    use crate::trailer::accumulation::{AccumulationPolicy, Accumulator};
+    use crate::trailer::spec::{ISSUE_STATUS_SPEC, ISSUE_TYPE_SPEC, ToMap};
+
+    const PRIORITY_SPEC: TrailerSpec = TrailerSpec {
+        key: Cow::Borrowed("Dit-priority"),
+        accumulation: AccumulationPolicy::Latest,
+    };
+
+    fn metadata() -> std::collections::HashMap<String, ValueAccumulator> {
+        let mut metadata = [ISSUE_STATUS_SPEC, ISSUE_TYPE_SPEC, PRIORITY_SPEC].into_map();
+        metadata.process_all([
+            Trailer::new("Dit-status", "open"),
+            Trailer::new("Dit-type", "bug"),
+            Trailer::new("Dit-priority", "3"),
+        ]);
+        metadata
+    }
+
+    #[test]
+    fn numeric_comparison() {
+        assert!(ValueMatcher::Less(4).matches(&TrailerValue::Int(3)));
+        assert!(!ValueMatcher::Less(3).matches(&TrailerValue::Int(3)));
+        assert!(ValueMatcher::Greater(2).matches(&TrailerValue::Int(3)));
+        assert!(!ValueMatcher::Greater(3).matches(&TrailerValue::Int(3)));
+        assert!(!ValueMatcher::Greater(2).matches(&TrailerValue::from_slice("foo")));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex() {
+        let matcher = ValueMatcher::Regex(regex::Regex::new("^o.e").expect("Invalid regex"));
+        assert!(matcher.matches(&TrailerValue::from_slice("open")));
+        assert!(!matcher.matches(&TrailerValue::from_slice("closed")));
+    }
+
+    #[test]
+    fn combinators() {
+        let metadata = metadata();
+        let status = |s: &str| {
+            Filter::from(TrailerFilter::new(
+                ISSUE_STATUS_SPEC,
+                ValueMatcher::Equals(TrailerValue::from_slice(s)),
+            ))
+        };
+        let priority = Filter::from(TrailerFilter::new(PRIORITY_SPEC, ValueMatcher::Greater(2)));
+
+        assert!(status("open").matches(&metadata));
+        assert!(!Filter::Not(Box::new(status("open"))).matches(&metadata));
+        assert!(Filter::And(vec![status("open"), priority]).matches(&metadata));
+        assert!(!Filter::And(vec![status("open"), status("closed")]).matches(&metadata));
+        assert!(Filter::Or(vec![status("closed"), status("open")]).matches(&metadata));
+        assert!(!Filter::Or(vec![status("closed")]).matches(&metadata));
+        assert!(Filter::And(vec![]).matches(&metadata));
+    }
+
+    #[test]
+    fn specs() {
+        let filter = Filter::Or(vec![
+            TrailerFilter::new(ISSUE_STATUS_SPEC, ValueMatcher::Any).into(),
+            Filter::Not(Box::new(
+                TrailerFilter::new(ISSUE_TYPE_SPEC, ValueMatcher::Any).into(),
+            )),
+        ]);
+        let keys: Vec<_> = filter.specs().into_iter().map(|s| s.key.as_ref()).collect();
+        assert_eq!(keys, ["Dit-status", "Dit-type"]);
+    }
+}
@@ -0,0 +1,603 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+
+//! Textual issue queries
+//!
+//! This module provides a parser for a small query language, producing
+//! [Filter]s. A query consists of terms of the form `<key><op><value>`, e.g.
+//! `status=open`, where `<op>` is one of the following operators:
+//!
+//!  * `=`: the value equals the one given,
+//!  * `~`: the value contains the one given,
+//!  * `<` and `>`: the value is an integer less or greater than the one given,
+//!  * `=~`: the value matches the regular expression given (requires the
+//!    `regex` feature).
+//!
+//! A key without an operator and value matches issues with any value for the
+//! key. Values containing whitespace or parentheses may be enclosed in double
+//! quotes, with `\"` and `\\` as escape sequences.
+//!
+//! Terms may be negated by prefixing them with either `-` or `not`. Terms
+//! separated by whitespace or `and` must all match, while `or` requires only
+//! one side to match. `and` binds stronger than `or`. Parentheses may be used
+//! for grouping, up to a depth of [MAX_NESTING]. For example, the query
+//!
+//! ```text
+//! status=open type~bug -label=wontfix (priority>2 or assignee=alice)
+//! ```
+//!
+//! matches all open bugs not labeled "wontfix" which either have a priority
+//! greater than two or are assigned to alice.
+//!
+//! Keys without a `-` are short keys referring to `Dit-` trailers, e.g. the key
+//! `status` refers to the trailer `Dit-status`. Other keys are used verbatim.
+//! Trailers for which a specification exists in [spec] are
+//! accumulated according to that specification. All other trailers are
+//! accumulated using [AccumulationPolicy::Latest].
+//!
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use super::TrailerValue;
+use super::accumulation::AccumulationPolicy;
+use super::filter::{Filter, TrailerFilter, ValueMatcher};
+use super::spec::{self, TrailerSpec};
+
+/// Maximum number of nested groups and negations in a query
+///
+pub const MAX_NESTING: usize = 64;
+
+/// Parse a query
+///
+/// An empty query results in a [Filter] matching all issues.
+///
+pub fn parse(query: &str) -> Result<Filter<'static>, ParseError> {
+    let mut parser = Parser {
+        text: query,
+        pos: 0,
+        depth: 0,
+    };
+
+    parser.skip_whitespace();
+    if parser.peek().is_none() {
+        return Ok(Filter::And(Vec::new()));
+    }
+
+    let filter = parser.parse_or()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        Some(c) => Err(parser.error(Reason::UnexpectedChar(c))),
+        None => Ok(filter),
+    }
+}
+
+impl FromStr for Filter<'static> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+/// Retrieve the [TrailerSpec] for a key used in a query
+///
+pub fn spec_for_key(key: &str) -> TrailerSpec<'static> {
+    let key = if key.contains('-') {
+        key.to_owned()
+    } else {
+        format!("Dit-{key}")
+    };
+
+    [
+        spec::ISSUE_TYPE_SPEC,
+        spec::ISSUE_STATUS_SPEC,
+        spec::ISSUE_LABEL_SPEC,
+        spec::ISSUE_ASSIGNEE_SPEC,
+    ]
+    .into_iter()
+    .find(|s| s.key == key)
+    .unwrap_or(TrailerSpec {
+        key: Cow::Owned(key),
+        accumulation: AccumulationPolicy::Latest,
+    })
+}
+
+/// Error type for query parsing
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// Byte offset in the query at which the error was encountered
+    pub position: usize,
+    /// Reason for the error
+    pub reason: Reason,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.reason, self.position)
+    }
+}
+
+/// Reason for a [ParseError]
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reason {
+    /// The query ended unexpectedly
+    UnexpectedEnd,
+    /// An unexpected character was encountered
+    UnexpectedChar(char),
+    /// A parenthesis was not closed
+    UnclosedParen,
+    /// A quoted value was not terminated
+    UnterminatedQuote,
+    /// An operator was not followed by a value
+    MissingValue,
+    /// A value for a numeric comparison is not an integer
+    InvalidNumber(String),
+    /// A regular expression is invalid or not supported
+    InvalidRegex(String),
+    /// Groups or negations are nested deeper than [MAX_NESTING]
+    TooDeep,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of query"),
+            Self::UnexpectedChar(c) => write!(f, "unexpected character '{c}'"),
+            Self::UnclosedParen => write!(f, "unclosed parenthesis"),
+            Self::UnterminatedQuote => write!(f, "unterminated quote"),
+            Self::MissingValue => write!(f, "missing value"),
+            Self::InvalidNumber(s) => write!(f, "invalid number '{s}'"),
+            Self::InvalidRegex(s) => write!(f, "invalid regex: {s}"),
+            Self::TooDeep => write!(f, "query nested too deeply"),
+        }
+    }
+}
+
+/// Recursive descent parser for queries
+///
+struct Parser<'q> {
+    text: &'q str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// Parse a disjunction of conjunctions
+    ///
+    fn parse_or(&mut self) -> Result<Filter<'static>, ParseError> {
+        let mut filters = vec![self.parse_and()?];
+        while self.keyword("or") {
+            filters.push(self.parse_and()?);
+        }
+        Ok(if filters.len() == 1 {
+            filters.remove(0)
+        } else {
+            Filter::Or(filters)
+        })
+    }
+
+    /// Parse a conjunction of terms
+    ///
+    fn parse_and(&mut self) -> Result<Filter<'static>, ParseError> {
+        let mut filters = vec![self.parse_unary()?];
+        loop {
+            self.skip_whitespace();
+            if matches!(self.peek(), None | Some(')')) || self.at_keyword("or") {
+                break;
+            }
+            self.keyword("and");
+            filters.push(self.parse_unary()?);
+        }
+        Ok(if filters.len() == 1 {
+            filters.remove(0)
+        } else {
+            Filter::And(filters)
+        })
+    }
+
+    /// Parse a potentially negated term or group
+    ///
+    fn parse_unary(&mut self) -> Result<Filter<'static>, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.keyword("not") || self.eat('-') {
+            return self
+                .nested(start, Self::parse_unary)
+                .map(|f| Filter::Not(Box::new(f)));
+        }
+
+        if self.eat('(') {
+            let filter = self.nested(start, Self::parse_or)?;
+            self.skip_whitespace();
+            if !self.eat(')') {
+                return Err(ParseError {
+                    position: start,
+                    reason: Reason::UnclosedParen,
+                });
+            }
+            return Ok(filter);
+        }
+
+        self.parse_term()
+    }
+
+    /// Parse a nested group or negation
+    ///
+    /// `start` is the position of the group or negation, which is reported
+    /// if the nesting limit is exceeded.
+    ///
+    fn nested(
+        &mut self,
+        start: usize,
+        parse: impl FnOnce(&mut Self) -> Result<Filter<'static>, ParseError>,
+    ) -> Result<Filter<'static>, ParseError> {
+        if self.depth >= MAX_NESTING {
+            return Err(ParseError {
+                position: start,
+                reason: Reason::TooDeep,
+            });
+        }
+        self.depth += 1;
+        let res = parse(self);
+        self.depth -= 1;
+        res
+    }
+
+    /// Parse a single term
+    ///
+    fn parse_term(&mut self) -> Result<Filter<'static>, ParseError> {
+        let key = self.take_while(|c| c.is_alphanumeric() || c == '-');
+        if key.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => self.error(Reason::UnexpectedChar(c)),
+                None => self.error(Reason::UnexpectedEnd),
+            });
+        }
+        let spec = spec_for_key(key);
+
+        let matcher = if self.eat('=') {
+            if self.eat('~') {
+                let start = self.pos;
+                let value = self.parse_value()?;
+                regex_matcher(&value).map_err(|reason| ParseError {
+                    position: start,
+                    reason,
+                })?
+            } else {
+                ValueMatcher::Equals(TrailerValue::from_slice(&self.parse_value()?))
+            }
+        } else if self.eat('~') {
+            ValueMatcher::Contains(self.parse_value()?)
+        } else if self.eat('<') {
+            ValueMatcher::Less(self.parse_number()?)
+        } else if self.eat('>') {
+            ValueMatcher::Greater(self.parse_number()?)
+        } else {
+            match self.peek() {
+                Some(c) if !c.is_whitespace() && c != ')' => {
+                    return Err(self.error(Reason::UnexpectedChar(c)));
+                }
+                _ => ValueMatcher::Any,
+            }
+        };
+
+        Ok(TrailerFilter::new(spec, matcher).into())
+    }
+
+    /// Parse an integer value
+    ///
+    fn parse_number(&mut self) -> Result<i64, ParseError> {
+        let start = self.pos;
+        let value = self.parse_value()?;
+        value.parse().map_err(|_| ParseError {
+            position: start,
+            reason: Reason::InvalidNumber(value),
+        })
+    }
+
+    /// Parse a plain or quoted value
+    ///
+    fn parse_value(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        if !self.eat('"') {
+            let value = self.take_while(|c| !c.is_whitespace() && c != '(' && c != ')');
+            if value.is_empty() {
+                return Err(self.error(Reason::MissingValue));
+            }
+            return Ok(value.to_owned());
+        }
+
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => match self.next() {
+                    Some(c) => value.push(c),
+                    None => break,
+                },
+                Some(c) => value.push(c),
+                None => break,
+            }
+        }
+        Err(ParseError {
+            position: start,
+            reason: Reason::UnterminatedQuote,
+        })
+    }
+
+    /// Consume a keyword if it is next in the query
+    ///
+    /// Returns whether the keyword was consumed.
+    ///
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let res = self.at_keyword(keyword);
+        if res {
+            self.pos += keyword.len();
+        }
+        res
+    }
+
+    /// Check whether a keyword is next in the query
+    ///
+    fn at_keyword(&self, keyword: &str) -> bool {
+        self.rest()
+            .strip_prefix(keyword)
+            .map(|r| {
+                matches!(r.chars().next(), None | Some('(' | ')'))
+                    || r.starts_with(char::is_whitespace)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Consume a specific character if it is next in the query
+    ///
+    fn eat(&mut self, c: char) -> bool {
+        let res = self.peek() == Some(c);
+        if res {
+            self.pos += c.len_utf8();
+        }
+        res
+    }
+
+    /// Consume characters as long as they satisfy a predicate
+    ///
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &str {
+        let rest = &self.text[self.pos..];
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn error(&self, reason: Reason) -> ParseError {
+        ParseError {
+            position: self.pos,
+            reason,
+        }
+    }
+}
+
+/// Create a [ValueMatcher] for a regular expression
+///
+#[cfg(feature = "regex")]
+fn regex_matcher(value: &str) -> Result<ValueMatcher, Reason> {
+    regex::Regex::new(value)
+        .map(ValueMatcher::Regex)
+        .map_err(|e| Reason::InvalidRegex(e.to_string()))
+}
+
+/// Create a [ValueMatcher] for a regular expression
+///
+#[cfg(not(feature = "regex"))]
+fn regex_matcher(_value: &str) -> Result<ValueMatcher, Reason> {
+    Err(Reason::InvalidRegex(
+        "regex support is not enabled".to_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::trailer::Trailer;
+    use crate::trailer::accumulation::{Accumulator, ValueAccumulator};
+    use crate::trailer::spec::ToMap;
+
+    fn metadata(filter: &Filter, trailers: &[(&str, &str)]) -> HashMap<String, ValueAccumulator> {
+        let mut metadata = filter.specs().into_map();
+        metadata.process_all(trailers.iter().map(|(k, v)| Trailer::new(k, v)));
+        metadata
+    }
+
+    fn matches(query: &str, trailers: &[(&str, &str)]) -> bool {
+        let filter = parse(query).expect("Could not parse query");
+        filter.matches(&metadata(&filter, trailers))
+    }
+
+    #[test]
+    fn short_keys() {
+        assert_eq!(spec_for_key("status").key, "Dit-status");
+        assert_eq!(spec_for_key("Signed-off-by").key, "Signed-off-by");
+        assert!(matches!(
+            spec_for_key("label").accumulation,
+            AccumulationPolicy::Labels
+        ));
+    }
+
+    #[test]
+    fn simple_terms() {
+        let trailers = [
+            ("Dit-status", "open"),
+            ("Dit-type", "bugfix"),
+            ("Dit-priority", "3"),
+        ];
+        assert!(matches("status=open", &trailers));
+        assert!(!matches("status=closed", &trailers));
+        assert!(matches("type~bug", &trailers));
+        assert!(matches("priority>2", &trailers));
+        assert!(!matches("priority<3", &trailers));
+        assert!(matches("priority", &trailers));
+        assert!(!matches("assignee", &trailers));
+        assert!(matches("Dit-status=\"open\"", &trailers));
+        assert!(matches("", &trailers));
+    }
+
+    #[test]
+    fn combinations() {
+        let query = "status=open type~bug -label=wontfix (priority>2 or assignee=alice)";
+        let trailers = [
+            ("Dit-status", "open"),
+            ("Dit-type", "bug"),
+            ("Dit-label", "+ui"),
+            ("Dit-priority", "1"),
+            ("Dit-assignee", "alice"),
+        ];
+        assert!(matches(query, &trailers));
+        assert!(!matches(query, &trailers[..4]));
+
+        let mut trailers = trailers.to_vec();
+        trailers.push(("Dit-label", "+wontfix"));
+        assert!(!matches(query, &trailers));
+
+        assert!(matches("not status=closed and type=bug", &trailers));
+        assert!(matches("status=closed or type=bug", &trailers));
+        assert!(!matches(
+            "status=closed or type=bug status=closed",
+            &trailers
+        ));
+        assert!(matches("-(status=closed)", &trailers));
+    }
+
+    #[test]
+    fn quoted_values() {
+        let trailers = [("Dit-assignee", "Foo \"Bar\" (Baz)")];
+        assert!(matches(r#"assignee="Foo \"Bar\" (Baz)""#, &trailers));
+        assert!(matches(r#"assignee~"(Baz)""#, &trailers));
+    }
+
+    #[test]
+    fn keyword_prefixed_keys() {
+        let trailers = [("Dit-order", "1"), ("Dit-notes", "2")];
+        assert!(matches("order=1 notes=2", &trailers));
+        assert!(matches("order=2 or notes=2", &trailers));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex() {
+        let trailers = [("Dit-status", "open")];
+        assert!(matches("status=~^o.e", &trailers));
+        assert!(!matches("status=~^c", &trailers));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |query| match parse(query) {
+            Ok(_) => panic!("Parsed invalid query: {query}"),
+            Err(e) => e,
+        };
+
+        assert_eq!(
+            error("status="),
+            ParseError {
+                position: 7,
+                reason: Reason::MissingValue,
+            }
+        );
+        assert_eq!(
+            error("status=open (type=bug"),
+            ParseError {
+                position: 12,
+                reason: Reason::UnclosedParen,
+            }
+        );
+        assert_eq!(
+            error("status=open)"),
+            ParseError {
+                position: 11,
+                reason: Reason::UnexpectedChar(')'),
+            }
+        );
+        assert_eq!(
+            error("priority>high"),
+            ParseError {
+                position: 9,
+                reason: Reason::InvalidNumber("high".to_owned()),
+            }
+        );
+        assert_eq!(
+            error("status=\"open"),
+            ParseError {
+                position: 7,
+                reason: Reason::UnterminatedQuote,
+            }
+        );
+        assert_eq!(
+            error("status!open"),
+            ParseError {
+                position: 6,
+                reason: Reason::UnexpectedChar('!'),
+            }
+        );
+        assert_eq!(
+            error("status=open -"),
+            ParseError {
+                position: 13,
+                reason: Reason::UnexpectedEnd,
+            }
+        );
+        assert_eq!(error("status=~[").position, 8);
+    }
+
+    #[test]
+    fn nesting() {
+        let groups = |n| format!("{}status{}", "(".repeat(n), ")".repeat(n));
+        assert!(matches(&groups(MAX_NESTING), &[("Dit-status", "open")]));
+        let negations = format!("{}status", "-".repeat(MAX_NESTING));
+        assert!(matches(&negations, &[("Dit-status", "open")]));
+
+        let error = |query: String| match parse(&query) {
+            Ok(_) => panic!("Parsed too deeply nested query"),
+            Err(e) => e,
+        };
+        let too_deep = |position| ParseError {
+            position,
+            reason: Reason::TooDeep,
+        };
+        assert_eq!(error(groups(MAX_NESTING + 1)), too_deep(MAX_NESTING));
+        assert_eq!(error("(".repeat(100_000)), too_deep(MAX_NESTING));
+        assert_eq!(error("-".repeat(100_000)), too_deep(MAX_NESTING));
+        assert_eq!(error("not ".repeat(100_000)), too_deep(4 * MAX_NESTING));
+    }
+}
@@ -13,7 +13,7 @@
 //! well as specifications for some dit metadata tags.
 //!
 
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
 
 use super::accumulation::{AccumulationPolicy, SingleAccumulator, ValueAccumulator};
 
@@ -24,7 +24,7 @@ use super::accumulation::{AccumulationPolicy, SingleAccumulator, ValueAccumulato
 ///
 #[derive(Clone)]
 pub struct TrailerSpec<'k> {
-    pub key: &'k str,
+    pub key: Cow<'k, str>,
     pub accumulation: AccumulationPolicy,
 }
 
@@ -39,17 +39,31 @@ impl TrailerSpec<'_> {
 /// Metadata specification for an issue's type
 ///
 pub const ISSUE_TYPE_SPEC: TrailerSpec = TrailerSpec {
-    key: "Dit-type",
+    key: Cow::Borrowed("Dit-type"),
     accumulation: AccumulationPolicy::Latest,
 };
 
 /// Metadata specification for an issue's status
 ///
 pub const ISSUE_STATUS_SPEC: TrailerSpec = TrailerSpec {
-    key: "Dit-status",
+    key: Cow::Borrowed("Dit-status"),
     accumulation: AccumulationPolicy::Latest,
 };
 
+/// Metadata specification for an issue's labels
+///
+pub const ISSUE_LABEL_SPEC: TrailerSpec = TrailerSpec {
+    key: Cow::Borrowed("Dit-label"),
+    accumulation: AccumulationPolicy::Labels,
+};
+
+/// Metadata specification for an issue's assignees
+///
+pub const ISSUE_ASSIGNEE_SPEC: TrailerSpec = TrailerSpec {
+    key: Cow::Borrowed("Dit-assignee"),
+    accumulation: AccumulationPolicy::Labels,
+};
+
 /// Construct an accumulation map from a set of MetadataSpecifications
 ///
 /// This trait enables construction of maps from collections of
@@ -16,10 +16,10 @@ use crate::error::{self, ResultExt};
 pub trait Traversible<'t>: Base {
     /// [TraversalBuilder] type for this repository
     type TraversalBuilder: TraversalBuilder<
-        Oid = Self::Oid,
-        Error: Into<Self::InnerError>,
-        BuildError: Into<Self::InnerError>,
-    >;
+            Oid = Self::Oid,
+            Error: Into<Self::InnerError>,
+            BuildError: Into<Self::InnerError>,
+        >;
 
     /// Get an [Iterator] yielding commits, following the chain of first parents
     ///
@@ -50,6 +50,15 @@ impl<'t> Traversible<'t> for git2::Repository {
     }
 }
 
+#[cfg(feature = "gix")]
+impl<'t> Traversible<'t> for gix::Repository {
+    type TraversalBuilder = GixTraversalBuilder<'t>;
+
+    fn traversal_builder(&'t self) -> error::Result<Self::TraversalBuilder, Self::InnerError> {
+        Ok(gix::traverse::commit::topo::Builder::new(&self.objects))
+    }
+}
+
 /// Builder for a commit/message traversing [Iterator]
 pub trait TraversalBuilder: Sized {
     /// Object id type associated with this traversal builder
@@ -142,101 +151,101 @@ impl TraversalBuilder for git2::Revwalk<'_> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    use std::collections::{self, HashSet};
-
-    use crate::base::tests::TestOid;
-    use crate::error::tests::TestError;
-    use crate::object::tests::{TestObject, TestOdb};
+/// [TraversalBuilder] for a [gix::Repository]
+#[cfg(feature = "gix")]
+pub type GixTraversalBuilder<'t> =
+    gix::traverse::commit::topo::Builder<&'t gix::OdbHandle, fn(&gix::oid) -> bool>;
 
-    impl<'t, T> Traversible<'t> for (T, TestOdb)
-    where
-        T: Base<Oid = <TestOdb as Base>::Oid, InnerError = <TestOdb as Base>::InnerError>,
-    {
-        type TraversalBuilder = <TestOdb as Traversible<'t>>::TraversalBuilder;
+#[cfg(feature = "gix")]
+impl<'t> TraversalBuilder for GixTraversalBuilder<'t> {
+    type Oid = gix::ObjectId;
 
-        fn traversal_builder(&'t self) -> error::Result<Self::TraversalBuilder, Self::InnerError> {
-            self.1.traversal_builder()
-        }
-    }
+    type Iter = std::iter::Map<
+        gix::traverse::commit::Topo<&'t gix::OdbHandle, fn(&gix::oid) -> bool>,
+        fn(
+            Result<gix::traverse::commit::Info, gix::traverse::commit::topo::Error>,
+        ) -> Result<gix::ObjectId, error::GixError>,
+    >;
 
-    impl<'t> Traversible<'t> for TestOdb {
-        type TraversalBuilder = TestTraversal<'t>;
+    type Error = error::GixError;
 
-        fn traversal_builder(&'t self) -> error::Result<Self::TraversalBuilder, Self::InnerError> {
-            Ok(TestTraversal {
-                db: self.ro_objects(),
-                heads: Default::default(),
-                ends: Default::default(),
-            })
-        }
-    }
+    type BuildError = error::GixError;
 
-    pub struct TestTraversal<'t> {
-        db: std::sync::RwLockReadGuard<'t, HashSet<TestObject>>,
-        heads: collections::BinaryHeap<TestOid>,
-        ends: HashSet<TestOid>,
+    fn with_heads(
+        self,
+        heads: impl IntoIterator<Item = impl Into<Self::Oid>>,
+    ) -> Result<Self, Self::BuildError> {
+        Ok(self.with_tips(heads.into_iter().map(Into::into)))
     }
 
-    impl TraversalBuilder for TestTraversal<'_> {
-        type Oid = TestOid;
-        type Error = TestError;
-        type Iter = Self;
-        type BuildError = TestError;
-
-        fn with_heads(
-            mut self,
-            heads: impl IntoIterator<Item = impl Into<Self::Oid>>,
-        ) -> Result<Self, Self::BuildError> {
-            self.heads.extend(heads.into_iter().map(Into::into));
-            Ok(self)
-        }
-
-        fn with_ends(
-            mut self,
-            ends: impl IntoIterator<Item = impl Into<Self::Oid>>,
-        ) -> Result<Self, Self::BuildError> {
-            self.ends.extend(ends.into_iter().map(Into::into));
-            Ok(self)
-        }
-
-        fn build(self) -> Result<Self::Iter, Self::BuildError> {
-            Ok(self)
-        }
+    fn with_ends(
+        self,
+        ends: impl IntoIterator<Item = impl Into<Self::Oid>>,
+    ) -> Result<Self, Self::BuildError> {
+        Ok(gix::traverse::commit::topo::Builder::with_ends(
+            self,
+            ends.into_iter().map(Into::into),
+        ))
     }
 
-    impl Iterator for TestTraversal<'_> {
-        type Item = Result<TestOid, TestError>;
-
-        fn next(&mut self) -> Option<Self::Item> {
-            use crate::object::commit::Commit;
-
-            let id = self.heads.pop()?;
-
-            let Some(object) = self.db.get(&id) else {
-                return Some(Err(TestError));
-            };
+    fn build(self) -> Result<Self::Iter, Self::BuildError> {
+        use gix::traverse::commit::{Parents, topo};
 
-            let TestObject::Commit(commit) = object else {
-                return Some(Err(TestError));
-            };
-
-            let ends = &self.ends;
-            let parents = commit
-                .parent_ids()
-                .into_iter()
-                .filter(|p| !ends.contains(&p));
-            self.heads.extend(parents);
-
-            // The same commit may be the parent of multiple commits we've
-            // alreaty yielded. We don't check for duplicates when pushing
-            // them.
-            self.heads.retain(|c| c < &id);
+        let iter = topo::Builder::build(
+            self.sorting(topo::Sorting::TopoOrder)
+                .parents(Parents::First),
+        )
+        .map_err(error::GixError::new)?;
+        Ok(iter.map(|i| i.map(|i| i.id).map_err(error::GixError::new)))
+    }
+}
 
-            Some(Ok(id))
-        }
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "gix")]
+    #[test]
+    fn gix_first_parents() {
+        use super::*;
+        use crate::object::{Database, commit::Commit as _};
+
+        let (_dir, repo) = crate::base::tests::gix_repository();
+        let commit = |message: &str, parents: &[gix::ObjectId]| {
+            repo.commit_builder(Database::find_commit)
+                .expect("Cannot create commit builder")
+                .with_parents(
+                    parents
+                        .iter()
+                        .map(|p| Database::find_commit(&repo, *p).expect("Cannot find parent")),
+                )
+                .build(message)
+                .expect("Cannot create commit")
+                .id()
+        };
+
+        let base = commit("Base", &[]);
+        let first = commit("First", &[base]);
+        let second = commit("Second", &[base]);
+        let merge = commit("Merge", &[first, second]);
+
+        let ids: Vec<_> = repo
+            .traversal_builder()
+            .expect("Could not create traversal builder")
+            .with_head(merge)
+            .and_then(TraversalBuilder::build)
+            .expect("Could not build traversal")
+            .collect::<Result<_, _>>()
+            .expect("Could not traverse");
+        assert_eq!(ids, [merge, first, base]);
+
+        let ids: Vec<_> = repo
+            .traversal_builder()
+            .expect("Could not create traversal builder")
+            .with_head(merge)
+            .and_then(|t| t.with_end(second))
+            .and_then(TraversalBuilder::build)
+            .expect("Could not build traversal")
+            .collect::<Result<_, _>>()
+            .expect("Could not traverse");
+        assert_eq!(ids, [merge, first]);
     }
 }
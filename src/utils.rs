@@ -0,0 +1,516 @@
+// git-dit - the distributed issue tracker for git
+// Copyright (C) 2025 Julian Ganz <neither@nut.email>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//! Library internal utilities
+
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Abbreviated names of the days of the week, starting with sunday
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// Abbreviated names of the months
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Broken down time in a given timezone
+///
+/// This type allows formatting of git signature times, which are given as
+/// seconds since the Unix epoch and an offset in minutes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    weekday: u32,
+    offset: i32,
+}
+
+impl DateTime {
+    /// Create a broken down time from a timestamp and an offset in minutes
+    pub fn new(time: i64, offset: i32) -> Self {
+        let local = time + i64::from(offset) * 60;
+        let days = local.div_euclid(86400);
+        let secs = local.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year,
+            month,
+            day,
+            hour: (secs / 3600) as u32,
+            minute: (secs / 60 % 60) as u32,
+            second: (secs % 60) as u32,
+            weekday: (days + 4).rem_euclid(7) as u32,
+            offset,
+        }
+    }
+
+    /// Retrieve a [fmt::Display] formatting the time as specified in RFC 5322
+    pub fn rfc5322(&self) -> impl fmt::Display + '_ {
+        Rfc5322(self)
+    }
+
+    /// Retrieve a [fmt::Display] formatting the time as specified in RFC 3339
+    pub fn rfc3339(&self) -> impl fmt::Display + '_ {
+        Rfc3339(self)
+    }
+}
+
+/// Helper for formatting a [DateTime] as specified in RFC 5322
+struct Rfc5322<'a>(&'a DateTime);
+
+impl fmt::Display for Rfc5322<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let t = self.0;
+        let sign = if t.offset < 0 { '-' } else { '+' };
+        let offset = t.offset.unsigned_abs();
+        write!(
+            f,
+            "{}, {} {} {:04} {:02}:{:02}:{:02} {sign}{:02}{:02}",
+            WEEKDAYS[t.weekday as usize],
+            t.day,
+            MONTHS[t.month as usize - 1],
+            t.year,
+            t.hour,
+            t.minute,
+            t.second,
+            offset / 60,
+            offset % 60,
+        )
+    }
+}
+
+/// Helper for formatting a [DateTime] as specified in RFC 3339
+struct Rfc3339<'a>(&'a DateTime);
+
+impl fmt::Display for Rfc3339<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let t = self.0;
+        let sign = if t.offset < 0 { '-' } else { '+' };
+        let offset = t.offset.unsigned_abs();
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{sign}{:02}:{:02}",
+            t.year,
+            t.month,
+            t.day,
+            t.hour,
+            t.minute,
+            t.second,
+            offset / 60,
+            offset % 60,
+        )
+    }
+}
+
+/// Helper for displaying text with HTML and XML special characters escaped
+///
+/// The text may be used both as element content and as attribute value
+/// enclosed in double quotes.
+pub(crate) struct Escaped<'a, T: fmt::Display + ?Sized>(pub &'a T);
+
+impl<T: fmt::Display + ?Sized> fmt::Display for Escaped<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Escaper<'f, 'g>(&'f mut fmt::Formatter<'g>);
+
+        impl fmt::Write for Escaper<'_, '_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                let mut rest = s;
+                while let Some(pos) = rest.find(['&', '<', '>', '"']) {
+                    self.0.write_str(&rest[..pos])?;
+                    self.0.write_str(match rest.as_bytes()[pos] {
+                        b'&' => "&amp;",
+                        b'<' => "&lt;",
+                        b'>' => "&gt;",
+                        _ => "&quot;",
+                    })?;
+                    rest = &rest[pos + 1..];
+                }
+                self.0.write_str(rest)
+            }
+        }
+
+        fmt::Write::write_fmt(&mut Escaper(f), format_args!("{}", self.0))
+    }
+}
+
+/// Parse a date and time as specified in RFC 5322
+///
+/// Returns the time in seconds since the Unix epoch and the timezone offset in
+/// minutes. Obsolete two-digit years and named timezones are accepted. Unknown
+/// timezones are interpreted as UTC.
+pub(crate) fn parse_rfc5322(text: &str) -> Option<(i64, i32)> {
+    let text = text.split_once(',').map(|(_, t)| t).unwrap_or(text);
+    let mut tokens = text.split_whitespace();
+
+    let day: u32 = tokens.next()?.parse().ok()?;
+    let month = tokens.next()?;
+    let month = MONTHS.iter().position(|m| m.eq_ignore_ascii_case(month))? as u32 + 1;
+    let year = tokens.next()?;
+    let year = match (year.len(), year.parse::<i64>().ok()?) {
+        (2, y) if y < 50 => y + 2000,
+        (2 | 3, y) => y + 1900,
+        (_, y) => y,
+    };
+
+    let mut clock = tokens.next()?.split(':');
+    let hour: u32 = clock.next()?.parse().ok()?;
+    let minute: u32 = clock.next()?.parse().ok()?;
+    let second: u32 = clock.next().map(str::parse).transpose().ok()?.unwrap_or(0);
+
+    let offset = match tokens.next().unwrap_or("+0000") {
+        zone if zone.starts_with(['+', '-'])
+            && zone.len() == 5
+            && zone[1..].bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            let value: i32 = zone[1..].parse().ok()?;
+            if value % 100 >= 60 {
+                return None;
+            }
+            let offset = value / 100 * 60 + value % 100;
+            if zone.starts_with('-') {
+                -offset
+            } else {
+                offset
+            }
+        }
+        "EDT" => -4 * 60,
+        "EST" | "CDT" => -5 * 60,
+        "CST" | "MDT" => -6 * 60,
+        "MST" | "PDT" => -7 * 60,
+        "PST" => -8 * 60,
+        _ => 0,
+    };
+
+    timestamp((year, month, day), (hour, minute, second), offset).map(|t| (t, offset))
+}
+
+/// Parse a date and time as specified in RFC 3339
+///
+/// Returns the time in seconds since the Unix epoch and the timezone offset in
+/// minutes. Fractions of seconds are ignored.
+#[cfg(any(test, feature = "json"))]
+pub(crate) fn parse_rfc3339(text: &str) -> Option<(i64, i32)> {
+    let (date, time) = text.trim().split_once(['T', 't', ' '])?;
+    let mut date = date.splitn(3, '-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: u32 = date.next()?.parse().ok()?;
+    let day: u32 = date.next()?.parse().ok()?;
+
+    let (clock, zone) = time
+        .find(['Z', 'z', '+', '-'])
+        .map(|i| time.split_at(i))
+        .unwrap_or((time, "Z"));
+    let mut clock = clock.split(':');
+    let hour: u32 = clock.next()?.parse().ok()?;
+    let minute: u32 = clock.next()?.parse().ok()?;
+    let second: u32 = clock.next()?.split('.').next()?.parse().ok()?;
+
+    let offset = match zone {
+        "Z" | "z" => 0,
+        zone => {
+            let (hours, minutes) = zone[1..].split_once(':')?;
+            let hours: u8 = hours.parse().ok()?;
+            let minutes: u8 = minutes.parse().ok()?;
+            if hours >= 24 || minutes >= 60 {
+                return None;
+            }
+            let offset = i32::from(hours) * 60 + i32::from(minutes);
+            if zone.starts_with('-') {
+                -offset
+            } else {
+                offset
+            }
+        }
+    };
+
+    timestamp((year, month, day), (hour, minute, second), offset).map(|t| (t, offset))
+}
+
+/// Versioned, line based text format for persisting data
+///
+/// The first line of a file in such a format is a header identifying the
+/// format and its version. An empty file is equivalent to one without any data.
+pub(crate) struct LineFormat {
+    /// Header identifying the format and its version
+    pub header: &'static str,
+    /// Name of the data persisted, used in error messages
+    pub name: &'static str,
+}
+
+impl LineFormat {
+    /// Load data from a file
+    ///
+    /// If the file does not exist, the default value is returned.
+    pub fn load<T: Default>(
+        &self,
+        path: &Path,
+        read: impl FnOnce(io::BufReader<fs::File>) -> io::Result<T>,
+    ) -> io::Result<T> {
+        match fs::File::open(path) {
+            Ok(file) => read(io::BufReader::new(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Default::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Save data to a file
+    ///
+    /// The data is written to a temporary file, which then replaces the file
+    /// at the given path. Thus, readers never observe a partially written
+    /// file. The name of the temporary file is unique to the process and the
+    /// call, which allows concurrent saves. Missing parent directories are
+    /// created.
+    pub fn save(
+        &self,
+        path: &Path,
+        write: impl FnOnce(&mut io::BufWriter<fs::File>) -> io::Result<()>,
+    ) -> io::Result<()> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut name = path.file_name().unwrap_or_default().to_owned();
+        name.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+        ));
+        let tmp = path.with_file_name(name);
+
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)?;
+        let mut file = io::BufWriter::new(file);
+        let res = write(&mut file)
+            .and_then(|_| file.into_inner().map_err(io::IntoInnerError::into_error))
+            .and_then(|f| f.sync_all())
+            .and_then(|_| fs::rename(&tmp, path));
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        res
+    }
+
+    /// Retrieve the lines following the header
+    ///
+    /// Lines are yielded along with their zero-based line numbers. If the input
+    /// is empty, `None` is returned.
+    pub fn lines(
+        &self,
+        reader: impl BufRead,
+    ) -> io::Result<Option<impl Iterator<Item = (usize, io::Result<String>)>>> {
+        let mut lines = reader.lines().enumerate();
+        let Some((n, line)) = lines.next() else {
+            return Ok(None);
+        };
+        if line? != self.header {
+            return Err(self.invalid(n));
+        }
+        Ok(Some(lines))
+    }
+
+    /// Write the header
+    pub fn write_header(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{}", self.header)
+    }
+
+    /// Create an error for a malformed line
+    pub fn invalid(&self, line: usize) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("malformed {} in line {}", self.name, line + 1),
+        )
+    }
+}
+
+/// Compute a timestamp from a date, a time and a timezone offset in minutes
+///
+/// Returns the time in seconds since the Unix epoch, or `None` if any of the
+/// components is out of range. Only years from 0 to 9999 are supported.
+fn timestamp(
+    (year, month, day): (i64, u32, u32),
+    (hour, minute, second): (u32, u32, u32),
+    offset: i32,
+) -> Option<i64> {
+    let valid = (0..=9999).contains(&year)
+        && (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && hour < 24
+        && minute < 60
+        && second < 60
+        && offset.abs() < 24 * 60;
+    if !valid {
+        return None;
+    }
+    days_from_civil(year, month, day)
+        .checked_mul(86400)?
+        .checked_add(i64::from(hour * 3600 + minute * 60 + second))?
+        .checked_sub(i64::from(offset) * 60)
+}
+
+/// Compute the number of days since the Unix epoch from year, month and day
+///
+/// Months and days are one-based.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Compute year, month and day from the number of days since the Unix epoch
+///
+/// Months and days are one-based.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc5322() {
+        let format = |time, offset| DateTime::new(time, offset).rfc5322().to_string();
+        assert_eq!(format(0, 0), "Thu, 1 Jan 1970 00:00:00 +0000");
+        assert_eq!(format(951782400, 0), "Tue, 29 Feb 2000 00:00:00 +0000");
+        assert_eq!(format(1700000000, -120), "Tue, 14 Nov 2023 20:13:20 -0200");
+        assert_eq!(format(1700000000, 330), "Wed, 15 Nov 2023 03:43:20 +0530");
+        assert_eq!(format(-1, 0), "Wed, 31 Dec 1969 23:59:59 +0000");
+    }
+
+    #[test]
+    fn rfc3339() {
+        let format = |time, offset| DateTime::new(time, offset).rfc3339().to_string();
+        assert_eq!(format(0, 0), "1970-01-01T00:00:00+00:00");
+        assert_eq!(format(1700000000, -120), "2023-11-14T20:13:20-02:00");
+        assert_eq!(format(1700000000, 330), "2023-11-15T03:43:20+05:30");
+        assert_eq!(
+            parse_rfc3339(&format(1700000000, -570)),
+            Some((1700000000, -570)),
+        );
+    }
+
+    #[test]
+    fn line_format() {
+        const FORMAT: LineFormat = LineFormat {
+            header: "test-format 1",
+            name: "test data",
+        };
+        let read = |reader: io::BufReader<fs::File>| -> io::Result<Vec<String>> {
+            match FORMAT.lines(reader)? {
+                Some(lines) => lines.map(|(_, l)| l).collect(),
+                None => Ok(Vec::new()),
+            }
+        };
+
+        let dir = tempfile::tempdir().expect("Could not create temporary directory");
+        let path = dir.path().join("sub/data");
+        assert!(FORMAT.load(&path, read).expect("Could not load").is_empty());
+
+        FORMAT
+            .save(&path, |w| {
+                FORMAT.write_header(&mut *w)?;
+                writeln!(w, "foo")
+            })
+            .expect("Could not save");
+        assert_eq!(FORMAT.load(&path, read).expect("Could not load"), ["foo"]);
+        let files: Vec<_> = fs::read_dir(dir.path().join("sub"))
+            .expect("Could not list directory")
+            .map(|e| e.expect("Could not read entry").file_name())
+            .collect();
+        assert_eq!(files, ["data"]);
+
+        assert!(FORMAT.lines("".as_bytes()).expect("Empty input").is_none());
+        let error = FORMAT
+            .lines("other 1\n".as_bytes())
+            .err()
+            .expect("Accepted wrong header");
+        assert_eq!(error.to_string(), "malformed test data in line 1");
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(
+            Escaped("<a href=\"x\">Q&A</a>").to_string(),
+            "&lt;a href=&quot;x&quot;&gt;Q&amp;A&lt;/a&gt;",
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_rfc5322("Thu, 1 Jan 1970 00:00:00 +0000"),
+            Some((0, 0))
+        );
+        assert_eq!(
+            parse_rfc5322("Tue, 14 Nov 2023 20:13:20 -0200"),
+            Some((1700000000, -120)),
+        );
+        assert_eq!(
+            parse_rfc5322("15 Nov 2023 03:43:20 +0530 (IST)"),
+            Some((1700000000, 330)),
+        );
+        assert_eq!(parse_rfc5322("29 Feb 00 00:00 GMT"), Some((951782400, 0)));
+        assert_eq!(parse_rfc5322("Tue, 14 Nov 2023"), None);
+        assert_eq!(parse_rfc5322("yesterday"), None);
+        assert_eq!(parse_rfc5322("1 Jan 99999999999999999 00:00 +0000"), None);
+        assert_eq!(parse_rfc5322("1 Jan 2023 9999999999999999:00 +0000"), None);
+        assert_eq!(parse_rfc5322("1 Jan 2023 24:00 +0000"), None);
+        assert_eq!(parse_rfc5322("1 Jan 2023 00:60 +0000"), None);
+        assert_eq!(parse_rfc5322("1 Jan 2023 00:00:60 +0000"), None);
+        assert_eq!(parse_rfc5322("32 Jan 2023 00:00 +0000"), None);
+        assert_eq!(parse_rfc5322("0 Jan 2023 00:00 +0000"), None);
+        assert_eq!(parse_rfc5322("1 Jan 2023 00:00 +0060"), None);
+        assert_eq!(parse_rfc5322("1 Jan 2023 00:00 +2400"), None);
+    }
+
+    #[test]
+    fn parse_iso() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some((0, 0)));
+        assert_eq!(
+            parse_rfc3339("2023-11-14T20:13:20.123-02:00"),
+            Some((1700000000, -120)),
+        );
+        assert_eq!(
+            parse_rfc3339("2023-11-15T03:43:20+05:30"),
+            Some((1700000000, 330)),
+        );
+        assert_eq!(parse_rfc3339("2023-11-14"), None);
+        assert_eq!(parse_rfc3339("2023-13-14T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("99999999999999999-01-01T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2023-01-32T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2023-01-01T24:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2023-01-01T00:60:00Z"), None);
+        assert_eq!(parse_rfc3339("2023-01-01T00:00:60Z"), None);
+        assert_eq!(parse_rfc3339("2023-01-01T00:00:00+24:00"), None);
+        assert_eq!(parse_rfc3339("2023-01-01T00:00:00+00:60"), None);
+        assert_eq!(parse_rfc3339("2023-01-01T00:00:00+99999999999:00"), None);
+    }
+}